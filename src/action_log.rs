@@ -0,0 +1,299 @@
+use crate::ethereum_actions::EthereumAction;
+use crate::WatchtowerConfig;
+
+use anyhow::Result;
+use ethers::types::{Address, H256, U256};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// append-only record of every action the watchtower decided on, written to the action log file (if configured) as one
+// json line each time the action moves on: once decided, once each pause transaction is sent and once finished. Lines
+// are never rewritten, so a crash mid-way still leaves the decision and every transaction already sent
+#[derive(Clone, Debug)]
+pub struct WatchtowerActionLog {
+    file_path: Option<String>,
+    lock: Arc<Mutex<()>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub enum ActionStatus {
+    // decided on, nothing sent yet
+    Started,
+    // a pause transaction was sent and is waiting to be confirmed
+    Sent,
+    // not taken as the same action (or a pause of every contract) was already pending
+    Dropped,
+    Succeeded,
+    Proposed,
+    Failed,
+    Skipped,
+    AlreadyPaused,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ActionRecord {
+    pub network: String,
+    pub action: EthereumAction,
+    pub check: Option<String>,
+    pub alert: Option<String>,
+    pub alert_level: String,
+    pub requested_at: Option<u64>,
+    pub started_at: Option<u64>,
+    pub finished_at: Option<u64>,
+    pub status: ActionStatus,
+    pub pauses: Vec<PauseRecord>,
+}
+
+// the outcome of pausing one contract, with every transaction tried along the way
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PauseRecord {
+    pub contract: String,
+    pub status: ActionStatus,
+    pub attempts: Vec<PauseAttempt>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct PauseAttempt {
+    pub account: Option<Address>,
+    pub safe: Option<Address>,
+    pub safe_tx_hash: Option<H256>,
+    pub tx_hash: Option<H256>,
+    pub gas_used: Option<U256>,
+    pub started_at: Option<u64>,
+    pub finished_at: Option<u64>,
+    pub error: Option<String>,
+}
+
+impl WatchtowerActionLog {
+    pub fn new(config: &WatchtowerConfig) -> Self {
+        WatchtowerActionLog {
+            file_path: config.action_log_file.clone(),
+            lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.file_path.is_some()
+    }
+
+    pub fn append(&self, record: &ActionRecord) -> Result<()> {
+        let file_path = match &self.file_path {
+            Some(file_path) => file_path,
+            None => return Ok(()),
+        };
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+
+        // written in a single call and synced before returning, so records are never interleaved or left in a buffer
+        let _lock = self.lock.lock().unwrap();
+        let mut file = OpenOptions::new().create(true).append(true).open(file_path)?;
+        file.write_all(line.as_bytes())?;
+        file.sync_data()?;
+        Ok(())
+    }
+
+    // the latest state of the latest actions, oldest first. Every line of an action replaces the one before it, so an
+    // action cut short by a crash is left as it last was. Lines that cannot be read (such as one cut short by a crash)
+    // are skipped
+    pub fn read_recent(&self, limit: usize) -> Result<Vec<ActionRecord>> {
+        let file_path = match &self.file_path {
+            Some(file_path) if Path::new(file_path).exists() => file_path,
+            _ => return Ok(vec![]),
+        };
+        let mut records: Vec<ActionRecord> = vec![];
+        let mut in_progress = HashMap::new();
+        for record in fs::read_to_string(file_path)?
+            .lines()
+            .filter_map(|line| serde_json::from_str::<ActionRecord>(line).ok())
+        {
+            // an action is told apart by when it was requested, as the same one is never pending twice
+            if record.status == ActionStatus::Dropped {
+                records.push(record);
+                continue;
+            }
+            let key = (record.network.clone(), record.action.clone(), record.requested_at);
+            let position = match in_progress.remove(&key) {
+                Some(position) => {
+                    records[position] = record;
+                    position
+                }
+                None => {
+                    records.push(record);
+                    records.len() - 1
+                }
+            };
+            if records[position].is_in_progress() {
+                in_progress.insert(key, position);
+            }
+        }
+        let skip = records.len().saturating_sub(limit);
+        Ok(records.into_iter().skip(skip).collect())
+    }
+}
+
+impl ActionRecord {
+    pub fn is_in_progress(&self) -> bool {
+        self.status == ActionStatus::Started || self.status == ActionStatus::Sent
+    }
+}
+
+pub fn unix_time(time: SystemTime) -> Option<u64> {
+    time.duration_since(UNIX_EPOCH).ok().map(|time| time.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_config;
+    use std::env;
+
+    fn action_log(name: &str) -> WatchtowerActionLog {
+        let file_path = env::temp_dir().join(format!("watchtower_action_log_{}_{}", std::process::id(), name));
+        let _ = fs::remove_file(&file_path);
+        let mut config = test_config();
+        config.action_log_file = Some(file_path.to_string_lossy().to_string());
+        WatchtowerActionLog::new(&config)
+    }
+
+    fn record(action: EthereumAction, status: ActionStatus) -> ActionRecord {
+        ActionRecord {
+            network: String::from("mainnet"),
+            action,
+            check: Some(String::from("ethereum_chain")),
+            alert: Some(String::from("no new blocks")),
+            alert_level: String::from("Error"),
+            requested_at: Some(1),
+            started_at: Some(2),
+            finished_at: Some(3),
+            status: status.clone(),
+            pauses: vec![PauseRecord {
+                contract: String::from("state"),
+                status,
+                attempts: vec![PauseAttempt {
+                    account: Some(Address::repeat_byte(1)),
+                    tx_hash: Some(H256::repeat_byte(2)),
+                    ..Default::default()
+                }],
+                error: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn records_are_read_back() {
+        let action_log = action_log("read_back");
+        assert!(action_log.read_recent(10).unwrap().is_empty());
+
+        action_log
+            .append(&record(EthereumAction::PauseState, ActionStatus::Succeeded))
+            .unwrap();
+        action_log
+            .append(&record(EthereumAction::PauseAll, ActionStatus::Failed))
+            .unwrap();
+
+        let records = action_log.read_recent(10).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].action, EthereumAction::PauseState);
+        assert_eq!(records[0].status, ActionStatus::Succeeded);
+        assert_eq!(records[0].pauses[0].attempts[0].tx_hash, Some(H256::repeat_byte(2)));
+        assert_eq!(records[1].action, EthereumAction::PauseAll);
+        assert_eq!(records[1].status, ActionStatus::Failed);
+    }
+
+    #[test]
+    fn only_the_latest_records_are_read() {
+        let action_log = action_log("latest");
+        for action in [
+            EthereumAction::PauseState,
+            EthereumAction::PauseGateway,
+            EthereumAction::PausePortal,
+        ] {
+            action_log.append(&record(action, ActionStatus::Succeeded)).unwrap();
+        }
+
+        let actions: Vec<EthereumAction> = action_log
+            .read_recent(2)
+            .unwrap()
+            .into_iter()
+            .map(|record| record.action)
+            .collect();
+        assert_eq!(actions, vec![EthereumAction::PauseGateway, EthereumAction::PausePortal]);
+    }
+
+    #[test]
+    fn unreadable_lines_are_skipped() {
+        let action_log = action_log("unreadable");
+        action_log
+            .append(&record(EthereumAction::PauseState, ActionStatus::Succeeded))
+            .unwrap();
+        // a line cut short by a crash
+        let file_path = action_log.file_path.clone().unwrap();
+        let mut file = OpenOptions::new().append(true).open(&file_path).unwrap();
+        file.write_all(b"{\"network\":\"mainn").unwrap();
+
+        let records = action_log.read_recent(10).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].action, EthereumAction::PauseState);
+    }
+
+    #[test]
+    fn actions_are_read_as_they_last_were() {
+        let action_log = action_log("progress");
+        let mut started = record(EthereumAction::PauseAll, ActionStatus::Started);
+        started.finished_at = None;
+        started.pauses = vec![];
+        let mut sent = record(EthereumAction::PauseAll, ActionStatus::Sent);
+        sent.finished_at = None;
+        sent.pauses[0].status = ActionStatus::Sent;
+        let mut dropped = record(EthereumAction::PauseAll, ActionStatus::Dropped);
+        dropped.pauses = vec![];
+        let mut unfinished = sent.clone();
+        unfinished.action = EthereumAction::PauseState;
+        unfinished.requested_at = Some(4);
+        for record in [
+            &started,
+            &dropped,
+            &sent,
+            &record(EthereumAction::PauseAll, ActionStatus::Succeeded),
+            &unfinished,
+        ] {
+            action_log.append(record).unwrap();
+        }
+
+        // the pause of every contract finished, while the crash cut the pause of the state contract short once sent
+        let records = action_log.read_recent(10).unwrap();
+        let statuses: Vec<(EthereumAction, ActionStatus)> = records
+            .iter()
+            .map(|record| (record.action.clone(), record.status.clone()))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                (EthereumAction::PauseAll, ActionStatus::Succeeded),
+                (EthereumAction::PauseAll, ActionStatus::Dropped),
+                (EthereumAction::PauseState, ActionStatus::Sent),
+            ]
+        );
+        assert_eq!(records[2].pauses[0].attempts[0].tx_hash, Some(H256::repeat_byte(2)));
+        assert_eq!(action_log.read_recent(1).unwrap()[0].action, EthereumAction::PauseState);
+    }
+
+    #[test]
+    fn nothing_is_written_without_a_file() {
+        let mut config = test_config();
+        config.action_log_file = None;
+        let action_log = WatchtowerActionLog::new(&config);
+
+        assert!(!action_log.is_enabled());
+        action_log
+            .append(&record(EthereumAction::PauseState, ActionStatus::Succeeded))
+            .unwrap();
+        assert!(action_log.read_recent(10).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,499 @@
+use crate::action_log::unix_time;
+use crate::alerts::AlertLevel;
+use crate::ethereum_actions::EthereumAction;
+use crate::network::WatchtowerNetwork;
+use crate::server::json_response;
+
+use anyhow::Result;
+use hyper::header::AUTHORIZATION;
+use hyper::{Body, Method, Request, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::oneshot;
+
+pub static AUDIT_LOG_TARGET: &str = "audit";
+pub static ADMIN_API_CHECK: &str = "admin api";
+pub static ADMIN_ACTIONS_LIMIT: usize = 100;
+
+// sent to the main thread to reload the config file, which answers with the changes once the reload is applied
+pub type ReloadRequest = oneshot::Sender<Result<Vec<String>>>;
+
+// runtime controls for operators, served under /admin/ to callers presenting the bearer token. Requests acting on
+// alerts or contracts name the network they are for, which can be left out when only one network is watched
+#[derive(Clone, Debug)]
+pub struct WatchtowerAdmin {
+    token: String,
+    networks: Vec<WatchtowerNetwork>,
+    reload_sender: UnboundedSender<ReloadRequest>,
+}
+
+#[derive(Deserialize)]
+struct AcknowledgeRequest {
+    network: Option<String>,
+    check: String,
+}
+
+#[derive(Deserialize)]
+struct SilenceRequest {
+    network: Option<String>,
+    check: String,
+    seconds: u64,
+}
+
+#[derive(Deserialize)]
+struct ActionRequest {
+    network: Option<String>,
+    action: EthereumAction,
+}
+
+impl WatchtowerAdmin {
+    pub fn new(token: String, networks: Vec<WatchtowerNetwork>, reload_sender: UnboundedSender<ReloadRequest>) -> Self {
+        WatchtowerAdmin {
+            token,
+            networks,
+            reload_sender,
+        }
+    }
+
+    // every call is written to the audit log, including the ones turned away
+    pub async fn handle(&self, request: Request<Body>, remote_address: SocketAddr) -> Response<Body> {
+        let method = request.method().clone();
+        let path = request.uri().path().to_string();
+        if !self.is_authorized(&request) {
+            log::warn!(
+                target: AUDIT_LOG_TARGET,
+                "{} {} from {} rejected: missing or invalid token",
+                method,
+                path,
+                remote_address
+            );
+            return json_response((StatusCode::UNAUTHORIZED, json!({ "error": "unauthorized" })));
+        }
+
+        let body = match hyper::body::to_bytes(request.into_body()).await {
+            Ok(body) => body,
+            Err(e) => {
+                let error = format!("Failed to read request body: {e}");
+                log::warn!(target: AUDIT_LOG_TARGET, "{} {} from {} failed: {}", method, path, remote_address, error);
+                return json_response((StatusCode::BAD_REQUEST, json!({ "error": error })));
+            }
+        };
+        let (status, details) = self.route(&method, &path, &body).await;
+        log::info!(
+            target: AUDIT_LOG_TARGET,
+            "{} {} from {} (body: {}) returned {}",
+            method,
+            path,
+            remote_address,
+            String::from_utf8_lossy(&body),
+            status
+        );
+        json_response((status, details))
+    }
+
+    async fn route(&self, method: &Method, path: &str, body: &[u8]) -> (StatusCode, Value) {
+        match (method, path) {
+            (&Method::GET, "/admin/alerts") => self.list_alerts(),
+            (&Method::POST, "/admin/alerts/acknowledge") => match parse_body::<AcknowledgeRequest>(body) {
+                Ok(request) => self.acknowledge(request),
+                Err(e) => e,
+            },
+            (&Method::POST, "/admin/alerts/silence") => match parse_body::<SilenceRequest>(body) {
+                Ok(request) => self.silence(request),
+                Err(e) => e,
+            },
+            (&Method::GET, "/admin/actions") => self.list_actions(),
+            (&Method::POST, "/admin/actions") => match parse_body::<ActionRequest>(body) {
+                Ok(request) => self.trigger_action(request),
+                Err(e) => e,
+            },
+            (&Method::GET, "/admin/contracts") => self.contract_states().await,
+            (&Method::POST, "/admin/reload") => self.reload().await,
+            _ => (StatusCode::NOT_FOUND, json!({ "error": "not found" })),
+        }
+    }
+
+    fn is_authorized(&self, request: &Request<Body>) -> bool {
+        match request
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+        {
+            Some(token) => tokens_match(self.token.as_bytes(), token.as_bytes()),
+            None => false,
+        }
+    }
+
+    // the network a request is for, which must be named when more than one network is watched
+    fn get_network(&self, name: &Option<String>) -> Result<&WatchtowerNetwork, (StatusCode, Value)> {
+        match name {
+            Some(name) => self
+                .networks
+                .iter()
+                .find(|network| &network.name == name)
+                .ok_or_else(|| {
+                    (
+                        StatusCode::BAD_REQUEST,
+                        json!({ "error": format!("Unknown network: {}", name) }),
+                    )
+                }),
+            None if self.networks.len() == 1 => Ok(&self.networks[0]),
+            None => Err((
+                StatusCode::BAD_REQUEST,
+                json!({ "error": "More than one network is watched, the request must name its network." }),
+            )),
+        }
+    }
+
+    fn list_alerts(&self) -> (StatusCode, Value) {
+        let mut active = Vec::new();
+        let mut silenced = Vec::new();
+        for network in &self.networks {
+            for alert in network.alerts.get_active_alerts() {
+                active.push(json!({
+                    "network": network.name,
+                    "check": alert.check,
+                    "level": format!("{:?}", alert.level),
+                    "text": alert.text,
+                    "first_raised": unix_time(alert.first_raised),
+                    "last_raised": unix_time(alert.last_raised),
+                    "count": alert.count,
+                    "acknowledged": alert.acknowledged,
+                }));
+            }
+            for (check, until) in network.alerts.get_silenced_checks() {
+                silenced.push(json!({
+                    "network": network.name,
+                    "check": check,
+                    "until": unix_time(until),
+                }));
+            }
+        }
+        (StatusCode::OK, json!({ "active": active, "silenced": silenced }))
+    }
+
+    fn acknowledge(&self, request: AcknowledgeRequest) -> (StatusCode, Value) {
+        let network = match self.get_network(&request.network) {
+            Ok(network) => network,
+            Err(e) => return e,
+        };
+        if network.alerts.acknowledge(&request.check) {
+            (
+                StatusCode::OK,
+                json!({ "network": network.name, "acknowledged": request.check }),
+            )
+        } else {
+            (
+                StatusCode::NOT_FOUND,
+                json!({ "error": format!("No active alert for check: {}", request.check) }),
+            )
+        }
+    }
+
+    fn silence(&self, request: SilenceRequest) -> (StatusCode, Value) {
+        let network = match self.get_network(&request.network) {
+            Ok(network) => network,
+            Err(e) => return e,
+        };
+        network
+            .alerts
+            .silence(&request.check, Duration::from_secs(request.seconds));
+        (
+            StatusCode::OK,
+            json!({ "network": network.name, "silenced": request.check, "seconds": request.seconds }),
+        )
+    }
+
+    // manual actions go through the same queue as the ones triggered by checks, so one already pending (or a pending
+    // pause of every contract) is not queued again
+    fn trigger_action(&self, request: ActionRequest) -> (StatusCode, Value) {
+        let network = match self.get_network(&request.network) {
+            Ok(network) => network,
+            Err(e) => return e,
+        };
+        network.alerts.alert(
+            format!("Manual {:?} requested through the admin API.", request.action),
+            AlertLevel::Warn,
+        );
+        let queued = network
+            .actions
+            .for_check(ADMIN_API_CHECK)
+            .action(request.action.clone(), Some(AlertLevel::Error));
+        if queued {
+            (
+                StatusCode::ACCEPTED,
+                json!({ "network": network.name, "queued": format!("{:?}", request.action) }),
+            )
+        } else {
+            (
+                StatusCode::CONFLICT,
+                json!({ "network": network.name, "already_pending": format!("{:?}", request.action) }),
+            )
+        }
+    }
+
+    // the latest records of each network's action log
+    fn list_actions(&self) -> (StatusCode, Value) {
+        let mut details = Map::new();
+        for network in &self.networks {
+            let actions = match network.actions.get_recent_actions(ADMIN_ACTIONS_LIMIT) {
+                Ok(actions) => json!(actions),
+                Err(e) => json!({ "error": format!("Failed to read action log: {e}") }),
+            };
+            details.insert(network.name.clone(), actions);
+        }
+        (StatusCode::OK, Value::Object(details))
+    }
+
+    async fn contract_states(&self) -> (StatusCode, Value) {
+        let mut details = Map::new();
+        for network in &self.networks {
+            let (state, portal, gateway) = tokio::join!(
+                network.clients.state_contract.is_paused(),
+                network.clients.portal_contract.is_paused(),
+                network.clients.gateway_contract.is_paused(),
+            );
+            details.insert(
+                network.name.clone(),
+                json!({
+                    "state": pause_details(state),
+                    "portal": pause_details(portal),
+                    "gateway": pause_details(gateway),
+                }),
+            );
+        }
+        (StatusCode::OK, Value::Object(details))
+    }
+
+    async fn reload(&self) -> (StatusCode, Value) {
+        let (reloaded, wait_for_reload) = oneshot::channel();
+        if self.reload_sender.send(reloaded).is_err() {
+            return (
+                StatusCode::SERVICE_UNAVAILABLE,
+                json!({ "error": "Watchtower is shutting down." }),
+            );
+        }
+        match wait_for_reload.await {
+            Ok(Ok(changes)) => (StatusCode::OK, json!({ "reloaded": true, "changes": changes })),
+            Ok(Err(e)) => (
+                StatusCode::BAD_REQUEST,
+                json!({ "reloaded": false, "error": e.to_string() }),
+            ),
+            Err(_) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                json!({ "error": "Watchtower is shutting down." }),
+            ),
+        }
+    }
+}
+
+fn parse_body<T: DeserializeOwned>(body: &[u8]) -> Result<T, (StatusCode, Value)> {
+    serde_json::from_slice(body).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            json!({ "error": format!("Invalid request body: {e}") }),
+        )
+    })
+}
+
+fn pause_details(paused: Result<bool>) -> Value {
+    match paused {
+        Ok(paused) => json!({ "paused": paused }),
+        Err(e) => json!({ "error": e.to_string() }),
+    }
+}
+
+// compares every byte so the time taken does not reveal how much of the token was right
+fn tokens_match(expected: &[u8], provided: &[u8]) -> bool {
+    expected.len() == provided.len()
+        && expected
+            .iter()
+            .zip(provided)
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{capture_logs, get_logs, start_actions, start_test_network, test_config, TestContract};
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+    use tokio::sync::mpsc;
+
+    static TOKEN: &str = "secret-token";
+
+    fn admin(network: &WatchtowerNetwork) -> WatchtowerAdmin {
+        let (reload_sender, _) = mpsc::unbounded_channel();
+        WatchtowerAdmin::new(TOKEN.to_string(), vec![network.clone()], reload_sender)
+    }
+
+    // requests from a different port in every test, to tell their audit log lines apart
+    async fn send(
+        admin: &WatchtowerAdmin,
+        port: u16,
+        authorization: Option<&str>,
+        method: Method,
+        path: &str,
+        body: Value,
+    ) -> (StatusCode, Value) {
+        let mut request = Request::builder().method(method).uri(path);
+        if let Some(authorization) = authorization {
+            request = request.header(AUTHORIZATION, authorization);
+        }
+        let body = match body {
+            Value::Null => Body::empty(),
+            body => Body::from(body.to_string()),
+        };
+        let response = admin
+            .handle(request.body(body).unwrap(), ([10, 0, 0, 1], port).into())
+            .await;
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    fn audit_lines(port: u16) -> Vec<String> {
+        let remote_address = format!("from 10.0.0.1:{port} ");
+        get_logs(AUDIT_LOG_TARGET)
+            .into_iter()
+            .filter(|line| line.contains(&remote_address))
+            .collect()
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn only_the_bearer_token_is_authorized() {
+        capture_logs();
+        let test_network = start_test_network(test_config()).await;
+        let admin = admin(&test_network.network);
+        let bearer = format!("Bearer {TOKEN}");
+
+        for authorization in [
+            None,
+            Some("Bearer wrong-token"),
+            Some(TOKEN),
+            Some("Bearer secret-toke"),
+        ] {
+            let (status, body) = send(&admin, 9001, authorization, Method::GET, "/admin/alerts", Value::Null).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+            assert_eq!(body["error"], "unauthorized");
+        }
+        let (status, body) = send(&admin, 9001, Some(&bearer), Method::GET, "/admin/alerts", Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!({ "active": [], "silenced": [] }));
+
+        // every call is audited, the rejected ones included
+        let lines = audit_lines(9001);
+        assert_eq!(lines.len(), 5);
+        for line in &lines[..4] {
+            assert_eq!(
+                line,
+                "GET /admin/alerts from 10.0.0.1:9001 rejected: missing or invalid token"
+            );
+        }
+        assert_eq!(
+            lines[4],
+            "GET /admin/alerts from 10.0.0.1:9001 (body: ) returned 200 OK"
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn actions_already_pending_are_not_queued() {
+        capture_logs();
+        let mut test_network = start_test_network(test_config()).await;
+        let contract = Arc::new(TestContract {
+            send_delay: Duration::from_millis(100),
+            ..Default::default()
+        });
+        let (actions, _) = start_actions(contract.clone());
+        test_network.network.actions = actions.clone();
+        let admin = admin(&test_network.network);
+        let bearer = format!("Bearer {TOKEN}");
+
+        let request = json!({ "action": "PauseAll" });
+        let (status, body) = send(&admin, 9002, Some(&bearer), Method::POST, "/admin/actions", request).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(body, json!({ "network": "default", "queued": "PauseAll" }));
+
+        // the pause of every contract is still in progress
+        let request = json!({ "action": "PauseState" });
+        let (status, body) = send(&admin, 9002, Some(&bearer), Method::POST, "/admin/actions", request).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body, json!({ "network": "default", "already_pending": "PauseState" }));
+
+        let request = json!({ "network": "mainnet", "action": "PauseState" });
+        let (status, body) = send(&admin, 9002, Some(&bearer), Method::POST, "/admin/actions", request).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "Unknown network: mainnet");
+
+        let request = json!({ "action": "PauseEverything" });
+        let (status, _) = send(&admin, 9002, Some(&bearer), Method::POST, "/admin/actions", request).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        actions.shutdown().await.unwrap();
+        assert_eq!(contract.pauses_sent.load(Ordering::SeqCst), 3);
+        let lines = audit_lines(9002);
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[0],
+            r#"POST /admin/actions from 10.0.0.1:9002 (body: {"action":"PauseAll"}) returned 202 Accepted"#
+        );
+        assert!(lines[1].ends_with("returned 409 Conflict"));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn silenced_checks_are_listed() {
+        capture_logs();
+        let test_network = start_test_network(test_config()).await;
+        let admin = admin(&test_network.network);
+        let bearer = format!("Bearer {TOKEN}");
+
+        let request = json!({ "check": "Block production", "seconds": 600 });
+        let (status, body) = send(
+            &admin,
+            9003,
+            Some(&bearer),
+            Method::POST,
+            "/admin/alerts/silence",
+            request,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body,
+            json!({ "network": "default", "silenced": "Block production", "seconds": 600 })
+        );
+
+        let (status, body) = send(&admin, 9003, Some(&bearer), Method::GET, "/admin/alerts", Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        let silenced = body["silenced"].as_array().unwrap();
+        assert_eq!(silenced.len(), 1);
+        assert_eq!(silenced[0]["network"], "default");
+        assert_eq!(silenced[0]["check"], "Block production");
+        assert!(test_network
+            .network
+            .alerts
+            .get_silenced_checks()
+            .contains_key("Block production"));
+
+        // silencing has to say for how long
+        let request = json!({ "check": "Block production" });
+        let (status, body) = send(
+            &admin,
+            9003,
+            Some(&bearer),
+            Method::POST,
+            "/admin/alerts/silence",
+            request,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid request body"));
+        assert_eq!(audit_lines(9003).len(), 3);
+    }
+}
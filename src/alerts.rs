@@ -1,17 +1,19 @@
+use crate::metrics::WatchtowerMetrics;
 use crate::WatchtowerConfig;
 
 use anyhow::Result;
-use serde::Deserialize;
-use std::thread;
+use prometheus_client::encoding::text::Encode;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, SystemTime};
-use tokio::sync::mpsc::error::TryRecvError::{Disconnected, Empty};
 use tokio::sync::mpsc::{self, UnboundedSender};
+use tokio::sync::oneshot;
 
 static MIN_DURATION_FROM_START_TO_ERR: Duration = Duration::from_millis(60 * 60 * 1000);
 static THREAD_CONNECTIONS_ERR: &str = "Connections to the alerts thread have all closed.";
-static POLL_DURATION: Duration = Duration::from_millis(1000);
 
-#[derive(Deserialize, Clone, PartialEq, Eq, Debug)]
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Hash, Encode, Debug)]
 pub enum AlertLevel {
     None,
     Info,
@@ -21,31 +23,58 @@ pub enum AlertLevel {
 
 #[derive(Clone, Debug)]
 pub struct WatchtowerAlerts {
-    alert_sender: UnboundedSender<AlertParams>,
+    alert_sender: UnboundedSender<AlertMessage>,
+    metrics: WatchtowerMetrics,
+    check: Option<String>,
+    tracked: Arc<Mutex<TrackedAlerts>>,
+}
+
+// the latest warning or error raised by a check, kept until a run of the check raises nothing
+#[derive(Clone, Debug)]
+pub struct ActiveAlert {
+    pub check: String,
+    pub level: AlertLevel,
+    pub text: String,
+    pub first_raised: SystemTime,
+    pub last_raised: SystemTime,
+    pub count: u64,
+    pub acknowledged: bool,
+}
+
+#[derive(Debug, Default)]
+struct TrackedAlerts {
+    active: BTreeMap<String, ActiveAlert>,
+    silenced: BTreeMap<String, SystemTime>,
 }
 
 // TODO: buffer message alerts to avoid duplicates
 
 impl WatchtowerAlerts {
-    pub fn new(config: &WatchtowerConfig) -> Result<Self> {
+    pub fn new(config: &WatchtowerConfig, metrics: WatchtowerMetrics) -> Result<Self> {
         let start = SystemTime::now();
+        let log_tag = config.log_tag();
 
         // TODO: setup connection with alert messaging
 
         // start handler thread for alert function
-        let (tx, mut rx) = mpsc::unbounded_channel::<AlertParams>();
+        let (tx, mut rx) = mpsc::unbounded_channel::<AlertMessage>();
         tokio::spawn(async move {
             loop {
-                let received_result = rx.try_recv();
+                let received_result = rx.recv().await;
                 match received_result {
-                    Ok(params) => {
+                    Some(AlertMessage::Flush(flushed)) => {
+                        // everything sent before the flush request has been handled by now
+                        log::logger().flush();
+                        let _ = flushed.send(());
+                    }
+                    Some(AlertMessage::Alert(params)) => {
                         match params.level {
                             AlertLevel::None => {}
                             AlertLevel::Info => {
-                                log::info!("{}", params.text);
+                                log::info!("{}{}", log_tag, params.text);
                             }
                             AlertLevel::Warn => {
-                                log::warn!("{}", params.text);
+                                log::warn!("{}{}", log_tag, params.text);
                                 let min_time_elapsed = match SystemTime::now().duration_since(start) {
                                     Ok(d) => d > MIN_DURATION_FROM_START_TO_ERR,
                                     _ => true,
@@ -55,7 +84,7 @@ impl WatchtowerAlerts {
                                 }
                             }
                             AlertLevel::Error => {
-                                log::error!("{}", params.text);
+                                log::error!("{}{}", log_tag, params.text);
                                 let min_time_elapsed = match SystemTime::now().duration_since(start) {
                                     Ok(d) => d > MIN_DURATION_FROM_START_TO_ERR,
                                     _ => true,
@@ -66,30 +95,127 @@ impl WatchtowerAlerts {
                             }
                         }
                     }
-                    Err(recv_error) => {
-                        match recv_error {
-                            Disconnected => {
-                                log::error!("{}", THREAD_CONNECTIONS_ERR);
-                                // TODO: send error through communication channels
+                    None => {
+                        log::error!("{}", THREAD_CONNECTIONS_ERR);
+                        // TODO: send error through communication channels
 
-                                panic!("{}", THREAD_CONNECTIONS_ERR);
-                            }
-                            Empty => {
-                                // wait a bit until next try
-                                thread::sleep(POLL_DURATION);
-                            }
-                        }
+                        panic!("{}", THREAD_CONNECTIONS_ERR);
                     }
                 }
             }
         });
 
-        Ok(WatchtowerAlerts { alert_sender: tx })
+        Ok(WatchtowerAlerts {
+            alert_sender: tx,
+            metrics,
+            check: None,
+            tracked: Arc::new(Mutex::new(TrackedAlerts::default())),
+        })
+    }
+
+    // alerts sent through the returned copy are counted against the given check in the metrics
+    pub fn for_check(&self, check: &str) -> Self {
+        WatchtowerAlerts {
+            alert_sender: self.alert_sender.clone(),
+            metrics: self.metrics.clone(),
+            check: Some(check.to_string()),
+            tracked: self.tracked.clone(),
+        }
     }
 
     pub fn alert(&self, text: String, level: AlertLevel) {
+        if level != AlertLevel::None {
+            self.metrics.record_alert(&level, self.check.as_deref());
+        }
+
+        // warnings and errors from checks stay active until resolved, and are only logged while muted by an operator
+        let (text, level) = match (&self.check, &level) {
+            (Some(check), AlertLevel::Warn | AlertLevel::Error) => {
+                let mut tracked = self.tracked.lock().unwrap();
+                let now = SystemTime::now();
+                let active_alert = tracked.active.entry(check.clone()).or_insert_with(|| ActiveAlert {
+                    check: check.clone(),
+                    level: level.clone(),
+                    text: text.clone(),
+                    first_raised: now,
+                    last_raised: now,
+                    count: 0,
+                    acknowledged: false,
+                });
+                active_alert.level = level.clone();
+                active_alert.text = text.clone();
+                active_alert.last_raised = now;
+                active_alert.count += 1;
+                let acknowledged = active_alert.acknowledged;
+                let silenced = matches!(tracked.silenced.get(check), Some(until) if *until > now);
+                if silenced {
+                    (format!("{text} (silenced)"), AlertLevel::Info)
+                } else if acknowledged {
+                    (format!("{text} (acknowledged)"), AlertLevel::Info)
+                } else {
+                    (text, level)
+                }
+            }
+            _ => (text, level),
+        };
         let params = AlertParams { text, level };
-        self.alert_sender.send(params).unwrap();
+        if let Err(e) = self.alert_sender.send(AlertMessage::Alert(params)) {
+            if let AlertMessage::Alert(params) = e.0 {
+                log::error!("{} Dropped alert: {}", THREAD_CONNECTIONS_ERR, params.text);
+            }
+        }
+    }
+
+    // clears the active alert of this check unless it was raised again since the given time
+    pub fn resolve(&self, since: SystemTime) {
+        if let Some(check) = &self.check {
+            let mut tracked = self.tracked.lock().unwrap();
+            if matches!(tracked.active.get(check), Some(active_alert) if active_alert.last_raised < since) {
+                tracked.active.remove(check);
+            }
+        }
+    }
+
+    pub fn get_active_alerts(&self) -> Vec<ActiveAlert> {
+        self.tracked.lock().unwrap().active.values().cloned().collect()
+    }
+
+    // the active alert of the check alerts are sent for through this copy
+    pub fn get_active_alert(&self) -> Option<ActiveAlert> {
+        let check = self.check.as_ref()?;
+        self.tracked.lock().unwrap().active.get(check).cloned()
+    }
+
+    pub fn get_silenced_checks(&self) -> BTreeMap<String, SystemTime> {
+        let now = SystemTime::now();
+        let mut tracked = self.tracked.lock().unwrap();
+        tracked.silenced.retain(|_, until| *until > now);
+        tracked.silenced.clone()
+    }
+
+    // mutes the active alert of a check until it resolves, returning false if the check has no active alert
+    pub fn acknowledge(&self, check: &str) -> bool {
+        match self.tracked.lock().unwrap().active.get_mut(check) {
+            Some(active_alert) => {
+                active_alert.acknowledged = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // mutes all alerts of a check for the given duration, whether or not it currently has an active alert
+    pub fn silence(&self, check: &str, duration: Duration) {
+        let until = SystemTime::now() + duration;
+        self.tracked.lock().unwrap().silenced.insert(check.to_string(), until);
+    }
+
+    // waits for all alerts sent so far to be pushed out
+    pub async fn flush(&self) {
+        let (flushed, wait_for_flush) = oneshot::channel();
+        if self.alert_sender.send(AlertMessage::Flush(flushed)).is_ok() {
+            let _ = wait_for_flush.await;
+        }
     }
 }
 
@@ -98,3 +224,9 @@ struct AlertParams {
     text: String,
     level: AlertLevel,
 }
+
+#[derive(Debug)]
+enum AlertMessage {
+    Alert(AlertParams),
+    Flush(oneshot::Sender<()>),
+}
@@ -0,0 +1,178 @@
+use anyhow::Result;
+use std::cmp::{max, min};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub static BLOCK_TIMESTAMP_CACHE_SIZE: usize = 4096;
+
+// timestamps (in seconds) of the blocks already looked up on one chain, and the block each time frame was last resolved
+// to. A time frame only ever starts later, so each search picks up from where the last one ended and only looks at the
+// blocks produced since, which are mostly in the cache already. The oldest blocks are dropped once the cache is full
+#[derive(Clone, Debug, Default)]
+pub struct BlockTimestamps {
+    timestamps: Arc<Mutex<BTreeMap<u64, u64>>>,
+    time_frame_starts: Arc<Mutex<HashMap<u32, u64>>>,
+}
+
+impl BlockTimestamps {
+    pub fn new() -> Self {
+        BlockTimestamps::default()
+    }
+
+    pub fn get(&self, block_num: u64) -> Option<u64> {
+        self.timestamps.lock().unwrap().get(&block_num).copied()
+    }
+
+    pub fn insert(&self, block_num: u64, timestamp: u64) {
+        let mut timestamps = self.timestamps.lock().unwrap();
+        timestamps.insert(block_num, timestamp);
+        while timestamps.len() > BLOCK_TIMESTAMP_CACHE_SIZE {
+            let oldest = *timestamps.keys().next().unwrap();
+            timestamps.remove(&oldest);
+        }
+    }
+
+    // finds the first block between `low` and `latest_block_num` produced within the last `time_frame` seconds.
+    // Returns the block after the latest if none was
+    pub async fn find_block_from_time_frame<F, Fut>(
+        &self,
+        time_frame: u32,
+        low: u64,
+        latest_block_num: u64,
+        get_block_timestamp: F,
+    ) -> Result<u64>
+    where
+        F: Fn(u64) -> Fut,
+        Fut: Future<Output = Result<u64>>,
+    {
+        let previous_start = self.time_frame_starts.lock().unwrap().get(&time_frame).copied();
+        let low = match previous_start {
+            Some(previous_start) => max(low, min(previous_start, latest_block_num)),
+            None => low,
+        };
+        let block_num = find_block_from_time_frame(time_frame, low, latest_block_num, get_block_timestamp).await?;
+        self.time_frame_starts.lock().unwrap().insert(time_frame, block_num);
+        Ok(block_num)
+    }
+}
+
+// finds the first block between `low` and `latest_block_num` produced within the last `time_frame` seconds. Steps
+// forward from `low` in growing strides until a block in the time frame is found, then binary searches the last
+// stride, so a search starting close to the result stays short. Returns the block after the latest if none was
+async fn find_block_from_time_frame<F, Fut>(
+    time_frame: u32,
+    low: u64,
+    latest_block_num: u64,
+    get_block_timestamp: F,
+) -> Result<u64>
+where
+    F: Fn(u64) -> Fut,
+    Fut: Future<Output = Result<u64>>,
+{
+    let seconds_now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let target_timestamp = max(seconds_now, time_frame as u64) - time_frame as u64;
+
+    let mut low = low;
+    let mut high = latest_block_num;
+    if get_block_timestamp(high).await? < target_timestamp {
+        return Ok(high + 1);
+    }
+    let mut stride = 1;
+    while low < high {
+        let block_num = min(low + stride - 1, high);
+        if get_block_timestamp(block_num).await? < target_timestamp {
+            low = block_num + 1;
+            stride *= 2;
+        } else {
+            high = block_num;
+            break;
+        }
+    }
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if get_block_timestamp(mid).await? < target_timestamp {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    Ok(low)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_drops_oldest_blocks() {
+        let block_timestamps = BlockTimestamps::new();
+        for block_num in 0..(BLOCK_TIMESTAMP_CACHE_SIZE as u64 + 2) {
+            block_timestamps.insert(block_num, block_num * 12);
+        }
+        assert_eq!(block_timestamps.get(0), None);
+        assert_eq!(block_timestamps.get(1), None);
+        assert_eq!(block_timestamps.get(2), Some(24));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn finds_first_block_in_time_frame() {
+        // one block every 10 seconds, the latest produced just now
+        let seconds_now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let get_block_timestamp = |block_num: u64| async move { Ok(seconds_now - (1000 - block_num) * 10) };
+
+        let block_num = find_block_from_time_frame(95, 0, 1000, get_block_timestamp)
+            .await
+            .unwrap();
+        assert_eq!(block_num, 991);
+        let block_num = find_block_from_time_frame(100_000, 0, 1000, get_block_timestamp)
+            .await
+            .unwrap();
+        assert_eq!(block_num, 0);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn no_block_in_time_frame() {
+        let seconds_now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let get_block_timestamp = |_| async move { Ok(seconds_now - 3600) };
+
+        let block_num = find_block_from_time_frame(60, 0, 1000, get_block_timestamp)
+            .await
+            .unwrap();
+        assert_eq!(block_num, 1001);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn later_checks_are_served_from_the_cache() {
+        // one block every 10 seconds, block 1000 produced just now
+        let seconds_now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let block_timestamps = BlockTimestamps::new();
+        let chain_lookups = Mutex::new(Vec::new());
+        let get_block_timestamp = |block_num: u64| {
+            let cached = block_timestamps.get(block_num);
+            if cached.is_none() {
+                chain_lookups.lock().unwrap().push(block_num);
+            }
+            let timestamp = cached.unwrap_or(seconds_now + block_num * 10 - 10_000);
+            block_timestamps.insert(block_num, timestamp);
+            async move { Ok(timestamp) }
+        };
+
+        let block_num = block_timestamps
+            .find_block_from_time_frame(95, 0, 1000, get_block_timestamp)
+            .await
+            .unwrap();
+        assert_eq!(block_num, 991);
+        assert!(chain_lookups.lock().unwrap().len() > 10);
+
+        // the next check, once another block was produced, only has to look the new block up
+        chain_lookups.lock().unwrap().clear();
+        let block_num = block_timestamps
+            .find_block_from_time_frame(95, 0, 1001, get_block_timestamp)
+            .await
+            .unwrap();
+        assert_eq!(block_num, 991);
+        assert_eq!(*chain_lookups.lock().unwrap(), vec![1001]);
+    }
+}
@@ -0,0 +1,51 @@
+use crate::ethereum_watcher::ethereum_chain::EthereumChain;
+use crate::ethereum_watcher::ethereum_client::EthereumClient;
+use crate::ethereum_watcher::ethereum_signer::WatchtowerSigner;
+use crate::ethereum_watcher::gateway_contract::GatewayContract;
+use crate::ethereum_watcher::portal_contract::PortalContract;
+use crate::ethereum_watcher::safe_contract::SafeContract;
+use crate::ethereum_watcher::state_contract::StateContract;
+use crate::fuel_watcher::fuel_chain::FuelChain;
+use crate::WatchtowerConfig;
+
+use anyhow::Result;
+use ethers::providers::Provider;
+
+// chain and contract clients shared by the watchers and actions so they all reuse the same connections
+#[derive(Clone, Debug)]
+pub struct WatchtowerClients {
+    pub fuel_chain: FuelChain,
+    pub ethereum_chain: EthereumChain,
+    pub state_contract: StateContract,
+    pub portal_contract: PortalContract,
+    pub gateway_contract: GatewayContract,
+    pub safe_contract: Option<SafeContract>,
+    pub signers: Vec<WatchtowerSigner>,
+}
+
+impl WatchtowerClients {
+    pub async fn new(config: &WatchtowerConfig) -> Result<Self> {
+        let provider = Provider::new(EthereumClient::new(config)?);
+        let signers = WatchtowerSigner::from_config(config).await?;
+
+        let fuel_chain = FuelChain::new(config).await?;
+        let ethereum_chain = EthereumChain::new(config, provider.clone()).await?;
+        let state_contract = StateContract::new(config, provider.clone(), &signers).await?;
+        let portal_contract = PortalContract::new(config, provider.clone(), &signers).await?;
+        let gateway_contract = GatewayContract::new(config, provider.clone(), &signers).await?;
+        let safe_contract = match &config.ethereum_safe {
+            Some(safe_config) => Some(SafeContract::new(safe_config, provider, &signers).await?),
+            None => None,
+        };
+
+        Ok(WatchtowerClients {
+            fuel_chain,
+            ethereum_chain,
+            state_contract,
+            portal_contract,
+            gateway_contract,
+            safe_contract,
+            signers,
+        })
+    }
+}
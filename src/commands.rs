@@ -0,0 +1,264 @@
+use crate::action_log::WatchtowerActionLog;
+use crate::clients::WatchtowerClients;
+use crate::ethereum_actions::EthereumAction;
+use crate::ethereum_watcher::contract_pauser::ContractPauser;
+use crate::ethereum_watcher::ethereum_chain::EthereumChain;
+use crate::state::WatchtowerState;
+use crate::WatchtowerConfig;
+
+use anyhow::Result;
+use std::cmp::min;
+use std::fmt::Display;
+
+pub static BACKFILL_BLOCK_RANGE: u64 = 10000;
+
+// one off commands run from the command line against the same chains and contracts as the watchtower
+
+// prints the state of both chains, the contracts and the saved progress
+pub async fn print_status(config: &WatchtowerConfig) -> Result<()> {
+    let clients = WatchtowerClients::new(config).await?;
+    let state = WatchtowerState::load(config)?;
+
+    println!("Fuel chain");
+    println!(
+        "  latest block: {}",
+        show(clients.fuel_chain.get_latest_block_height().await)
+    );
+    println!(
+        "  seconds since last block: {}",
+        show(clients.fuel_chain.get_seconds_since_last_block().await)
+    );
+    println!("Ethereum chain");
+    println!(
+        "  latest block: {}",
+        show(clients.ethereum_chain.get_latest_block_number().await)
+    );
+    println!(
+        "  seconds since last block: {}",
+        show(clients.ethereum_chain.get_seconds_since_last_block().await)
+    );
+    if clients.signers.is_empty() {
+        println!("  account: not configured");
+    }
+    for signer in &clients.signers {
+        let address = EthereumChain::get_public_address(signer);
+        let balance = clients
+            .ethereum_chain
+            .get_account_balance(&address)
+            .await
+            .map(|balance| EthereumChain::get_units(balance, 18));
+        println!("  account: {} (balance: {})", address, show(balance));
+    }
+    println!("Contracts");
+    println!("  state paused: {}", show(clients.state_contract.is_paused().await));
+    println!("  portal paused: {}", show(clients.portal_contract.is_paused().await));
+    println!("  gateway paused: {}", show(clients.gateway_contract.is_paused().await));
+    println!("Progress");
+    match state.get_last_commit_check_block() {
+        Some(block_num) => println!("  commits checked up to block: {}", block_num),
+        None => println!("  commits checked up to block: none saved"),
+    }
+
+    Ok(())
+}
+
+// verifies every state contract commit made in a range of ethereum blocks, such as while the watchtower was down.
+// The saved progress of the watchtower is left as is
+pub async fn backfill(config: &WatchtowerConfig, from_block: u64, to_block: Option<u64>) -> Result<()> {
+    let clients = WatchtowerClients::new(config).await?;
+    let to_block = match to_block {
+        Some(to_block) => to_block,
+        None => clients.ethereum_chain.get_latest_block_number().await?,
+    };
+    if from_block > to_block {
+        return Err(anyhow::anyhow!(
+            "Invalid block range: {} is after {}.",
+            from_block,
+            to_block
+        ));
+    }
+
+    // logs are queried in chunks since most endpoints limit the range of a single query
+    let mut invalid_commits = vec![];
+    let mut checked = 0;
+    let mut block_num = from_block;
+    while block_num <= to_block {
+        let end_block_num = min(block_num + BACKFILL_BLOCK_RANGE - 1, to_block);
+        for hash in clients
+            .state_contract
+            .get_latest_commits(block_num, end_block_num)
+            .await?
+        {
+            checked += 1;
+            if !clients.fuel_chain.verify_block_commit(&hash).await? {
+                log::error!("An invalid commit was made on the state contract. Hash: {}", hash);
+                invalid_commits.push(hash);
+            }
+        }
+        log::info!(
+            "Checked state contract commits from block {} to {}.",
+            block_num,
+            end_block_num
+        );
+        block_num = end_block_num + 1;
+    }
+
+    if invalid_commits.is_empty() {
+        println!(
+            "Checked {} state contract commits from block {} to {}. All are valid.",
+            checked, from_block, to_block
+        );
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "Found {} invalid state contract commits from block {} to {}: {}",
+            invalid_commits.len(),
+            from_block,
+            to_block,
+            invalid_commits.join(", ")
+        ))
+    }
+}
+
+// pauses contracts by hand. With dry_run, the pauses are only simulated
+pub async fn pause(config: &WatchtowerConfig, action: EthereumAction, dry_run: bool) -> Result<()> {
+    if dry_run {
+        return simulate(config, action).await;
+    }
+
+    let clients = WatchtowerClients::new(config).await?;
+    let mut failed = vec![];
+    for contract_name in get_contract_names(&action) {
+        log::warn!("Pausing {} contract (requested from the command line).", contract_name);
+        let contract = get_pauser(&clients, contract_name);
+
+        // with a Safe holding the pauser role, the pause is proposed for the owners to confirm and execute
+        if let Some(safe) = &clients.safe_contract {
+            let result = match contract.get_pause_call() {
+                Ok((to, data)) => safe.propose(to, data).await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok((safe_tx_hash, _)) => println!(
+                    "Proposed pausing {} contract to Safe {:?}. Other Safe owners need to confirm and execute safe tx {:?}.",
+                    contract_name,
+                    safe.get_address(),
+                    safe_tx_hash
+                ),
+                Err(e) => {
+                    log::error!("{}", e);
+                    failed.push(contract_name);
+                }
+            }
+            continue;
+        }
+
+        // try each account in order until one of them gets the pause through
+        let mut paused = false;
+        for pauser in 0..contract.pauser_count().max(1) {
+            let result = contract.pause(pauser).await;
+            match result {
+                Ok(_) => {
+                    println!("Paused {} contract.", contract_name);
+                    paused = true;
+                    break;
+                }
+                Err(e) => log::error!("{}", e),
+            }
+        }
+        if !paused {
+            failed.push(contract_name);
+        }
+    }
+
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("Failed to pause contracts: {}", failed.join(", ")))
+    }
+}
+
+// checks each pause would go through with every configured account (or from the Safe once executed), without
+// sending any transaction
+pub async fn simulate(config: &WatchtowerConfig, action: EthereumAction) -> Result<()> {
+    let clients = WatchtowerClients::new(config).await?;
+    let mut failed = vec![];
+    for contract_name in get_contract_names(&action) {
+        let contract = get_pauser(&clients, contract_name);
+        if let Some(safe) = &clients.safe_contract {
+            let account = format!("Safe {:?}", safe.get_address());
+            let result = match contract.get_pause_call() {
+                Ok((to, data)) => safe.simulate(to, data, contract.get_abi()).await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(_) => println!("Pausing {} contract from {} would succeed.", contract_name, account),
+                Err(e) => {
+                    log::error!("Pausing {} contract would fail: {} ({})", contract_name, e, account);
+                    failed.push(format!("{} from {}", contract_name, account));
+                }
+            }
+            continue;
+        }
+        for pauser in 0..contract.pauser_count().max(1) {
+            let result = contract.simulate_pause(pauser).await;
+            let account = match &clients.signers.get(pauser) {
+                Some(signer) => EthereumChain::get_public_address(signer),
+                None => String::from("no account"),
+            };
+            match result {
+                Ok(_) => println!("Pausing {} contract from {} would succeed.", contract_name, account),
+                Err(e) => {
+                    log::error!("{} ({})", e, account);
+                    failed.push(format!("{} from {}", contract_name, account));
+                }
+            }
+        }
+    }
+
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "Pausing would fail for contracts: {}",
+            failed.join(", ")
+        ))
+    }
+}
+
+// prints the latest records of the action log, one json line each (oldest first)
+pub fn print_actions(config: &WatchtowerConfig, limit: usize) -> Result<()> {
+    let action_log = WatchtowerActionLog::new(config);
+    if !action_log.is_enabled() {
+        return Err(anyhow::anyhow!("No action_log_file is set for this network."));
+    }
+    for record in action_log.read_recent(limit)? {
+        println!("{}", serde_json::to_string(&record)?);
+    }
+    Ok(())
+}
+
+fn get_contract_names(action: &EthereumAction) -> Vec<&'static str> {
+    match action {
+        EthereumAction::None => vec![],
+        EthereumAction::PauseState => vec!["state"],
+        EthereumAction::PausePortal => vec!["portal"],
+        EthereumAction::PauseGateway => vec!["gateway"],
+        EthereumAction::PauseAll => vec!["state", "gateway", "portal"],
+    }
+}
+
+fn get_pauser<'a>(clients: &'a WatchtowerClients, contract_name: &str) -> &'a ContractPauser {
+    match contract_name {
+        "state" => clients.state_contract.get_pauser(),
+        "portal" => clients.portal_contract.get_pauser(),
+        _ => clients.gateway_contract.get_pauser(),
+    }
+}
+
+fn show<T: Display>(result: Result<T>) -> String {
+    match result {
+        Ok(value) => value.to_string(),
+        Err(e) => format!("unavailable ({e})"),
+    }
+}
@@ -2,51 +2,204 @@ use crate::alerts::AlertLevel;
 use crate::ethereum_actions::EthereumAction;
 
 use anyhow::Result;
-use serde::Deserialize;
-use std::{env, fs};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::env;
+
+mod sources;
+mod validation;
+
+use sources::{read_config_sources, split_networks};
+
+pub use validation::{validate_config, validate_config_live, validate_networks};
 
 pub static PRIVATE_KEY_ENV_VAR: &str = "WATCHTOWER_ETH_PRIVATE_KEY";
+pub static ADMIN_TOKEN_ENV_VAR: &str = "WATCHTOWER_ADMIN_TOKEN";
+pub static KEYSTORE_PASSWORD_ENV_VAR: &str = "WATCHTOWER_KEYSTORE_PASSWORD";
+pub static DEFAULT_NETWORK_NAME: &str = "default";
 
-#[derive(Deserialize, Clone, Debug)]
+// settings applied by restarting the watchers, everything else is only read on startup
+pub static RELOADABLE_SETTINGS: [&str; 2] = ["fuel_client_watcher", "ethereum_client_watcher"];
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct WatchtowerConfig {
-    pub fuel_graphql: String,
-    pub ethereum_rpc: String,
+    pub network: Option<String>,
+    #[serde(deserialize_with = "deserialize_one_or_many")]
+    pub fuel_graphql: Vec<String>,
+    #[serde(default = "default_graphql_quorum")]
+    pub fuel_graphql_quorum: usize,
+    #[serde(deserialize_with = "deserialize_one_or_many")]
+    pub ethereum_rpc: Vec<String>,
+    #[serde(default = "default_rpc_quorum")]
+    pub ethereum_rpc_quorum: usize,
+    #[serde(default = "default_rpc_max_lag")]
+    pub ethereum_rpc_max_lag: u64,
+    #[serde(default = "default_rpc_timeout")]
+    pub ethereum_rpc_timeout: u64,
+    #[serde(default = "default_rpc_rate_limit")]
+    pub ethereum_rpc_rate_limit: u32,
+    pub ethereum_ws: Option<String>,
     pub state_contract_address: String,
     pub portal_contract_address: String,
     pub gateway_contract_address: String,
-    pub ethereum_wallet_key: Option<String>,
+    #[serde(default, skip_serializing, deserialize_with = "deserialize_one_or_many")]
+    pub ethereum_wallet_key: Vec<String>,
+    #[serde(default, deserialize_with = "deserialize_one_or_many")]
+    pub ethereum_keystore: Vec<KeystoreConfig>,
+    #[serde(default, deserialize_with = "deserialize_one_or_many")]
+    pub ethereum_remote_signer: Vec<RemoteSignerConfig>,
+    pub ethereum_safe: Option<SafeConfig>,
+    pub ethereum_private_relay: Option<PrivateRelayConfig>,
     pub duplicate_alert_delay: u32,
+    #[serde(default = "default_max_watcher_restarts")]
+    pub max_watcher_restarts: u32,
+    pub state_file: Option<String>,
+    pub action_log_file: Option<String>,
+    pub heartbeat: Option<HeartbeatConfig>,
+    pub http_server: Option<HttpServerConfig>,
     pub fuel_client_watcher: FuelClientWatcher,
     pub ethereum_client_watcher: EthereumClientWatcher,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+impl WatchtowerConfig {
+    pub fn network_name(&self) -> &str {
+        self.network.as_deref().unwrap_or(DEFAULT_NETWORK_NAME)
+    }
+
+    // prefix for alerts and log lines, so the networks watched by one process can be told apart
+    pub fn log_tag(&self) -> String {
+        match &self.network {
+            Some(network) => format!("[{network}] "),
+            None => String::new(),
+        }
+    }
+
+    // whether any account to send actions from is set up (private keys, keystores or remote signers)
+    pub fn has_signer(&self) -> bool {
+        !self.ethereum_wallet_key.is_empty()
+            || !self.ethereum_keystore.is_empty()
+            || !self.ethereum_remote_signer.is_empty()
+    }
+
+    // whether any alert is set to take an action on the ethereum chain
+    pub fn has_alert_actions(&self) -> bool {
+        let fuel = &self.fuel_client_watcher;
+        let ethereum = &self.ethereum_client_watcher;
+        let mut actions = vec![
+            &fuel.connection_alert.alert_action,
+            &fuel.block_production_alert.alert_action,
+            &fuel.endpoint_consensus_alert.alert_action,
+            &ethereum.connection_alert.alert_action,
+            &ethereum.block_production_alert.alert_action,
+            &ethereum.account_funds_alert.alert_action,
+            &ethereum.invalid_state_commit_alert.alert_action,
+            &ethereum.endpoint_consensus_alert.alert_action,
+        ];
+        actions.extend(fuel.portal_withdraw_alerts.iter().map(|alert| &alert.alert_action));
+        actions.extend(fuel.gateway_withdraw_alerts.iter().map(|alert| &alert.alert_action));
+        actions.extend(ethereum.portal_deposit_alerts.iter().map(|alert| &alert.alert_action));
+        actions.extend(ethereum.gateway_deposit_alerts.iter().map(|alert| &alert.alert_action));
+        actions.into_iter().any(|action| *action != EthereumAction::None)
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct KeystoreConfig {
+    pub path: String,
+    pub password_file: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct RemoteSignerConfig {
+    pub url: String,
+    pub address: String,
+    #[serde(default = "default_remote_signer_timeout")]
+    pub timeout: u64,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct SafeConfig {
+    pub address: String,
+    pub transaction_service_url: String,
+    #[serde(default = "default_safe_transaction_service_timeout")]
+    pub timeout: u64,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct PrivateRelayConfig {
+    pub url: String,
+    #[serde(default = "default_private_relay_method")]
+    pub method: PrivateRelayMethod,
+    #[serde(default = "default_private_relay_fallback_blocks")]
+    pub fallback_blocks: u64,
+    #[serde(default = "default_private_relay_timeout")]
+    pub timeout: u64,
+}
+
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Debug)]
+pub enum PrivateRelayMethod {
+    PrivateTransaction,
+    Bundle,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct HeartbeatConfig {
+    pub url: String,
+    #[serde(default = "default_heartbeat_interval")]
+    pub interval: u64,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct HttpServerConfig {
+    pub address: String,
+    #[serde(skip_serializing)]
+    pub admin_token: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct FuelClientWatcher {
     pub connection_alert: GenericAlert,
     pub block_production_alert: BlockProductionAlert,
+    #[serde(default = "default_generic_alert")]
+    pub endpoint_consensus_alert: GenericAlert,
     pub portal_withdraw_alerts: Vec<WithdrawAlert>,
     pub gateway_withdraw_alerts: Vec<WithdrawAlert>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct EthereumClientWatcher {
     pub connection_alert: GenericAlert,
     pub block_production_alert: BlockProductionAlert,
     pub account_funds_alert: AccountFundsAlert,
     pub invalid_state_commit_alert: GenericAlert,
+    #[serde(default = "default_generic_alert")]
+    pub endpoint_consensus_alert: GenericAlert,
     pub portal_deposit_alerts: Vec<DepositAlert>,
     pub gateway_deposit_alerts: Vec<DepositAlert>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct GenericAlert {
     #[serde(default = "default_alert_level")]
     pub alert_level: AlertLevel,
     #[serde(default = "default_alert_action")]
     pub alert_action: EthereumAction,
+    pub check_interval: Option<u64>,
+    pub check_timeout: Option<u64>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct BlockProductionAlert {
     #[serde(default = "default_alert_level")]
     pub alert_level: AlertLevel,
@@ -54,9 +207,12 @@ pub struct BlockProductionAlert {
     pub alert_action: EthereumAction,
     #[serde(default = "default_max_block_time")]
     pub max_block_time: u32,
+    pub check_interval: Option<u64>,
+    pub check_timeout: Option<u64>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct AccountFundsAlert {
     #[serde(default = "default_alert_level")]
     pub alert_level: AlertLevel,
@@ -64,9 +220,12 @@ pub struct AccountFundsAlert {
     pub alert_action: EthereumAction,
     #[serde(default = "default_minimum_balance")]
     pub min_balance: f64,
+    pub check_interval: Option<u64>,
+    pub check_timeout: Option<u64>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct DepositAlert {
     #[serde(default = "default_alert_level")]
     pub alert_level: AlertLevel,
@@ -82,9 +241,12 @@ pub struct DepositAlert {
     pub time_frame: u32,
     #[serde(default = "default_amount")]
     pub amount: f64,
+    pub check_interval: Option<u64>,
+    pub check_timeout: Option<u64>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct WithdrawAlert {
     #[serde(default = "default_alert_level")]
     pub alert_level: AlertLevel,
@@ -100,9 +262,19 @@ pub struct WithdrawAlert {
     pub time_frame: u32,
     #[serde(default = "default_amount")]
     pub amount: f64,
+    pub check_interval: Option<u64>,
+    pub check_timeout: Option<u64>,
 }
 
 // deserialization default functions
+pub fn default_generic_alert() -> GenericAlert {
+    GenericAlert {
+        alert_level: default_alert_level(),
+        alert_action: default_alert_action(),
+        check_interval: None,
+        check_timeout: None,
+    }
+}
 pub fn default_alert_action() -> EthereumAction {
     EthereumAction::None
 }
@@ -133,27 +305,234 @@ pub fn default_time_frame() -> u32 {
 pub fn default_amount() -> f64 {
     1000.0
 }
+pub fn default_rpc_quorum() -> usize {
+    1
+}
+pub fn default_graphql_quorum() -> usize {
+    1
+}
+pub fn default_rpc_max_lag() -> u64 {
+    5
+}
+pub fn default_rpc_timeout() -> u64 {
+    10
+}
+pub fn default_rpc_rate_limit() -> u32 {
+    0
+}
+pub fn default_max_watcher_restarts() -> u32 {
+    10
+}
+pub fn default_remote_signer_timeout() -> u64 {
+    10
+}
+pub fn default_safe_transaction_service_timeout() -> u64 {
+    10
+}
+pub fn default_private_relay_method() -> PrivateRelayMethod {
+    PrivateRelayMethod::PrivateTransaction
+}
+pub fn default_private_relay_fallback_blocks() -> u64 {
+    5
+}
+pub fn default_private_relay_timeout() -> u64 {
+    10
+}
+pub fn default_heartbeat_interval() -> u64 {
+    60
+}
 
-// loads a config from a json file
-pub fn load_config(file_path: &str) -> Result<WatchtowerConfig> {
-    let json_string = fs::read_to_string(file_path)?;
-    let mut config: WatchtowerConfig = serde_json::from_str(&json_string)?;
+// deserializes either a single value (such as an endpoint) or a list of them
+pub fn deserialize_one_or_many<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany<T> {
+        One(T),
+        Many(Vec<T>),
+    }
+    match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(value) => Ok(vec![value]),
+        OneOrMany::Many(values) => Ok(values),
+    }
+}
 
-    // fill in the ethereum wallet key
-    if config.ethereum_wallet_key.is_some() {
-        log::warn!("Specifying the ethereum private key in the config file is not safe. Please use the {} environment variable instead.", PRIVATE_KEY_ENV_VAR);
-    } else {
+// lists every setting that differs between two configs (secrets are named but never shown). Fails if any of them can
+// only be applied with a restart, so a reload is either applied in full or not at all
+pub fn diff_config(old: &WatchtowerConfig, new: &WatchtowerConfig) -> Result<Vec<String>> {
+    let mut changes = Vec::new();
+    diff_values(
+        "",
+        &serde_json::to_value(old)?,
+        &serde_json::to_value(new)?,
+        &mut changes,
+    );
+    if old.ethereum_wallet_key != new.ethereum_wallet_key {
+        changes.push((String::from("ethereum_wallet_key"), String::from("changed")));
+    }
+    let old_admin_token = old.http_server.as_ref().and_then(|server| server.admin_token.as_ref());
+    let new_admin_token = new.http_server.as_ref().and_then(|server| server.admin_token.as_ref());
+    if old_admin_token != new_admin_token {
+        changes.push((String::from("http_server.admin_token"), String::from("changed")));
+    }
+
+    let restart_required: Vec<&str> = changes
+        .iter()
+        .map(|(path, _)| path.as_str())
+        .filter(|path| !RELOADABLE_SETTINGS.iter().any(|setting| path.starts_with(setting)))
+        .collect();
+    if !restart_required.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Changes to {} can only be applied with a restart.",
+            restart_required.join(", ")
+        ));
+    }
+    Ok(changes
+        .into_iter()
+        .map(|(path, change)| format!("{path}: {change}"))
+        .collect())
+}
+
+fn diff_values(path: &str, old: &Value, new: &Value, changes: &mut Vec<(String, String)>) {
+    match (old, new) {
+        (Value::Object(old_fields), Value::Object(new_fields)) => {
+            let mut keys: Vec<&String> = old_fields.keys().chain(new_fields.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let child_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                let old_value = old_fields.get(key).unwrap_or(&Value::Null);
+                let new_value = new_fields.get(key).unwrap_or(&Value::Null);
+                diff_values(&child_path, old_value, new_value, changes);
+            }
+        }
+        (Value::Array(old_items), Value::Array(new_items)) => {
+            for i in 0..old_items.len().max(new_items.len()) {
+                let old_value = old_items.get(i).unwrap_or(&Value::Null);
+                let new_value = new_items.get(i).unwrap_or(&Value::Null);
+                diff_values(&format!("{path}[{i}]"), old_value, new_value, changes);
+            }
+        }
+        _ => {
+            if old != new {
+                changes.push((path.to_string(), format!("{old} -> {new}")));
+            }
+        }
+    }
+}
+
+// loads the config of every network from one or more files (json, yaml or toml), each overriding the ones before
+// it, with any WATCHTOWER__ environment variable overrides applied on top. A config without a networks table is a
+// single unnamed network
+pub fn load_config(file_paths: &[String]) -> Result<Vec<WatchtowerConfig>> {
+    let mut configs = vec![];
+    for value in split_networks(read_config_sources(file_paths)?)? {
+        let network = value
+            .get("network")
+            .and_then(|network| network.as_str())
+            .map(String::from);
+        let config = load_network_config(value).map_err(|e| match &network {
+            Some(network) => anyhow::anyhow!("Network {}: {}", network, e),
+            None => e,
+        })?;
+        configs.push(config);
+    }
+
+    validate_networks(&configs)?;
+    Ok(configs)
+}
+
+fn load_network_config(value: Value) -> Result<WatchtowerConfig> {
+    // errors name the setting they are about, for example an unknown field nested in an alert
+    let mut config: WatchtowerConfig = serde_path_to_error::deserialize(value)?;
+    let log_tag = config.log_tag();
+
+    // fill in the ethereum wallet keys (comma separated in the environment variable), unless the keys are kept in
+    // keystores or by remote signers
+    if !config.ethereum_wallet_key.is_empty() {
+        log::warn!("{}Specifying the ethereum private key in the config file is not safe. Please use the {} environment variable, a keystore or a remote signer instead.", log_tag, PRIVATE_KEY_ENV_VAR);
+    } else if config.ethereum_keystore.is_empty() && config.ethereum_remote_signer.is_empty() {
         config.ethereum_wallet_key = match env::var(PRIVATE_KEY_ENV_VAR) {
-            Ok(wallet_key) => Some(wallet_key),
+            Ok(wallet_keys) => wallet_keys
+                .split(',')
+                .map(|wallet_key| wallet_key.trim().to_string())
+                .filter(|wallet_key| !wallet_key.is_empty())
+                .collect(),
             Err(_) => {
                 log::warn!(
-                    "{} environment variable not specified. Some alerts and actions have been disabled.",
+                    "{}{} environment variable not specified. Some alerts and actions have been disabled.",
+                    log_tag,
                     PRIVATE_KEY_ENV_VAR
                 );
-                None
+                vec![]
             }
         };
     }
 
+    // fill in the admin api token
+    if let Some(http_server) = &mut config.http_server {
+        if http_server.admin_token.is_some() {
+            log::warn!("{}Specifying the admin token in the config file is not safe. Please use the {} environment variable instead.", log_tag, ADMIN_TOKEN_ENV_VAR);
+        } else {
+            http_server.admin_token = env::var(ADMIN_TOKEN_ENV_VAR).ok();
+        }
+    }
+
+    validate_config(&config)?;
     Ok(config)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_config;
+
+    #[test]
+    fn config_errors_include_their_path() {
+        let mut value = serde_json::to_value(test_config()).unwrap();
+        value["ethereum_client_watcher"]["gateway_deposit_alerts"][0]["time_frme"] = Value::from(300);
+        let error = load_network_config(value).unwrap_err().to_string();
+        assert!(error.starts_with("ethereum_client_watcher.gateway_deposit_alerts[0]"));
+        assert!(error.contains("unknown field `time_frme`"));
+    }
+
+    #[test]
+    fn diff_lists_reloadable_changes() {
+        let old = test_config();
+        let mut new = test_config();
+        assert!(diff_config(&old, &new).unwrap().is_empty());
+
+        new.fuel_client_watcher.block_production_alert.max_block_time = 20;
+        new.ethereum_client_watcher.account_funds_alert.check_interval = Some(30);
+        let changes = diff_config(&old, &new).unwrap();
+        assert_eq!(
+            changes,
+            vec![
+                "ethereum_client_watcher.account_funds_alert.check_interval: 3600 -> 30",
+                "fuel_client_watcher.block_production_alert.max_block_time: 10 -> 20",
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_rejects_changes_that_need_a_restart() {
+        let old = test_config();
+        let mut new = test_config();
+        new.ethereum_rpc.push(String::from("https://<ethereum_rpc_other>"));
+        new.ethereum_wallet_key = vec![String::from("secret")];
+        new.fuel_client_watcher.block_production_alert.max_block_time = 20;
+
+        let error = diff_config(&old, &new).unwrap_err().to_string();
+        assert!(error.contains("ethereum_rpc[2]"));
+        assert!(error.contains("ethereum_wallet_key"));
+        assert!(!error.contains("secret"));
+        assert!(!error.contains("max_block_time"));
+    }
+}
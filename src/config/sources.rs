@@ -0,0 +1,226 @@
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+use std::env;
+use std::fs;
+use std::path::Path;
+
+pub static ENV_OVERRIDE_PREFIX: &str = "WATCHTOWER__";
+pub static ENV_OVERRIDE_SEPARATOR: &str = "__";
+pub static NETWORKS_KEY: &str = "networks";
+
+// reads each config file in order, with every file overriding the settings of the ones before it, then applies the
+// environment variable overrides on top
+pub fn read_config_sources(file_paths: &[String]) -> Result<Value> {
+    if file_paths.is_empty() {
+        return Err(anyhow::anyhow!("No config file specified."));
+    }
+
+    let mut config = Value::Object(Map::new());
+    for file_path in file_paths {
+        let value = read_config_file(file_path).map_err(|e| anyhow::anyhow!("{}: {}", file_path, e))?;
+        merge_values(&mut config, value);
+    }
+    apply_env_overrides(&mut config, env::vars())?;
+    Ok(config)
+}
+
+// splits a config with a networks table into the config of each network: the settings outside of the table are
+// shared by every network, and each network's own settings are layered on top of them
+pub fn split_networks(mut config: Value) -> Result<Vec<Value>> {
+    let networks = match config.as_object_mut().and_then(|config| config.remove(NETWORKS_KEY)) {
+        Some(Value::Object(networks)) => networks,
+        Some(_) => {
+            return Err(anyhow::anyhow!(
+                "{} must be a table of network names to settings.",
+                NETWORKS_KEY
+            ))
+        }
+        None => return Ok(vec![config]),
+    };
+    if networks.is_empty() {
+        return Err(anyhow::anyhow!("{} must list at least one network.", NETWORKS_KEY));
+    }
+
+    let mut network_configs = vec![];
+    for (name, settings) in networks {
+        let mut network_config = config.clone();
+        merge_values(&mut network_config, settings);
+        merge_values(&mut network_config, json!({ "network": name }));
+        network_configs.push(network_config);
+    }
+    Ok(network_configs)
+}
+
+// the format is chosen by the file extension
+fn read_config_file(file_path: &str) -> Result<Value> {
+    let contents = fs::read_to_string(file_path)?;
+    let extension = Path::new(file_path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    let value: Value = match extension.as_str() {
+        "json" => serde_json::from_str(&contents)?,
+        "yaml" | "yml" => serde_yaml::from_str(&contents)?,
+        "toml" => toml::from_str(&contents)?,
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Unsupported config file format (expected .json, .yaml, .yml or .toml)."
+            ))
+        }
+    };
+    match value {
+        Value::Object(_) => Ok(value),
+        _ => Err(anyhow::anyhow!("The config must be a map of settings.")),
+    }
+}
+
+// tables are merged setting by setting, anything else (including lists) is replaced as a whole
+fn merge_values(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(base_value) => merge_values(base_value, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+// WATCHTOWER__<SETTING>[__<SETTING or list index>...] sets any setting, for example WATCHTOWER__ETHEREUM_RPC or
+// WATCHTOWER__ETHEREUM_CLIENT_WATCHER__GATEWAY_DEPOSIT_ALERTS__0__AMOUNT. Values are read as json when they parse
+// as json (numbers, booleans, lists) and as plain strings otherwise
+fn apply_env_overrides(config: &mut Value, vars: impl Iterator<Item = (String, String)>) -> Result<()> {
+    let mut overrides: Vec<(String, String)> = vars.filter(|(name, _)| name.starts_with(ENV_OVERRIDE_PREFIX)).collect();
+    overrides.sort();
+    for (name, value) in overrides {
+        let path: Vec<String> = name[ENV_OVERRIDE_PREFIX.len()..]
+            .split(ENV_OVERRIDE_SEPARATOR)
+            .map(|segment| segment.to_lowercase())
+            .collect();
+        if path.iter().any(|segment| segment.is_empty()) {
+            return Err(anyhow::anyhow!("Invalid config override: {}", name));
+        }
+        let value = serde_json::from_str(&value).unwrap_or(Value::String(value));
+        set_value(config, &path, value).map_err(|e| anyhow::anyhow!("Invalid config override {}: {}", name, e))?;
+        log::info!("Config setting {} overridden by {}.", path.join("."), name);
+    }
+    Ok(())
+}
+
+fn set_value(config: &mut Value, path: &[String], value: Value) -> Result<()> {
+    let (segment, rest) = match path.split_first() {
+        Some(split) => split,
+        None => {
+            *config = value;
+            return Ok(());
+        }
+    };
+    match config {
+        Value::Array(list) => {
+            let len = list.len();
+            let entry = segment
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| list.get_mut(index))
+                .ok_or_else(|| anyhow::anyhow!("{} is not an index of the list ({} entries)", segment, len))?;
+            set_value(entry, rest, value)
+        }
+        Value::Object(table) => {
+            let entry = table
+                .entry(segment.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+            set_value(entry, rest, value)
+        }
+        _ => Err(anyhow::anyhow!("{} is not a setting of a table or list", segment)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_config_file(name: &str, contents: &str) -> String {
+        let file_path = env::temp_dir().join(format!("watchtower_sources_{}_{}", std::process::id(), name));
+        fs::write(&file_path, contents).unwrap();
+        file_path.to_string_lossy().to_string()
+    }
+
+    fn vars(vars: &[(&str, &str)]) -> impl Iterator<Item = (String, String)> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn later_files_override_earlier_ones() {
+        let base = write_config_file(
+            "base.json",
+            r#"{ "ethereum_rpc": ["https://a", "https://b"], "heartbeat": { "url": "https://monitor", "interval": 60 } }"#,
+        );
+        let overlay = write_config_file(
+            "overlay.yaml",
+            "ethereum_rpc: [\"https://c\"]\nheartbeat:\n  interval: 30\n",
+        );
+        let last = write_config_file("last.toml", "duplicate_alert_delay = 900\n");
+
+        let file_paths = [base, overlay, last];
+        let config = read_config_sources(&file_paths).unwrap();
+        file_paths
+            .iter()
+            .for_each(|file_path| fs::remove_file(file_path).unwrap());
+        assert_eq!(config["ethereum_rpc"], json!(["https://c"]));
+        assert_eq!(config["heartbeat"], json!({ "url": "https://monitor", "interval": 30 }));
+        assert_eq!(config["duplicate_alert_delay"], json!(900));
+    }
+
+    #[test]
+    fn unsupported_files_are_rejected() {
+        let file_paths = [write_config_file("config.ini", "ethereum_rpc = https://a\n")];
+        assert!(read_config_sources(&file_paths).is_err());
+        fs::remove_file(&file_paths[0]).unwrap();
+        assert!(read_config_sources(&[]).is_err());
+    }
+
+    #[test]
+    fn env_overrides_set_any_setting() {
+        let mut config = json!({
+            "ethereum_rpc": ["https://a"],
+            "ethereum_client_watcher": { "gateway_deposit_alerts": [{ "amount": 1000.0 }] },
+        });
+        apply_env_overrides(
+            &mut config,
+            vars(&[
+                ("WATCHTOWER__ETHEREUM_RPC", r#"["https://b", "https://c"]"#),
+                (
+                    "WATCHTOWER__ETHEREUM_CLIENT_WATCHER__GATEWAY_DEPOSIT_ALERTS__0__AMOUNT",
+                    "5.5",
+                ),
+                ("WATCHTOWER__NETWORK", "mainnet"),
+                ("OTHER__NETWORK", "ignored"),
+            ]),
+        )
+        .unwrap();
+
+        assert_eq!(config["ethereum_rpc"], json!(["https://b", "https://c"]));
+        assert_eq!(
+            config["ethereum_client_watcher"]["gateway_deposit_alerts"][0]["amount"],
+            json!(5.5)
+        );
+        assert_eq!(config["network"], json!("mainnet"));
+    }
+
+    #[test]
+    fn invalid_env_overrides_are_rejected() {
+        let mut config = json!({ "ethereum_rpc": ["https://a"], "duplicate_alert_delay": 900 });
+        assert!(apply_env_overrides(&mut config, vars(&[("WATCHTOWER__ETHEREUM_RPC__3", "https://b")])).is_err());
+        assert!(apply_env_overrides(&mut config, vars(&[("WATCHTOWER__DUPLICATE_ALERT_DELAY__X", "1")])).is_err());
+        assert!(apply_env_overrides(&mut config, vars(&[("WATCHTOWER____NETWORK", "mainnet")])).is_err());
+    }
+}
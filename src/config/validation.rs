@@ -0,0 +1,648 @@
+use super::{AccountFundsAlert, BlockProductionAlert, DepositAlert, GenericAlert, WatchtowerConfig, WithdrawAlert};
+use crate::ethereum_watcher::ethereum_signer::WatchtowerSigner;
+
+use anyhow::Result;
+use ethers::providers::{Http, Middleware, Provider, Ws};
+use ethers::types::H160;
+use ethers::utils::to_checksum;
+use fuels::prelude::Provider as FuelProvider;
+use reqwest::{Client, Url};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+pub static LIVE_CHECK_TIMEOUT: Duration = Duration::from_millis(10000);
+
+// every problem found in a config, each as the json path of the setting and the reason it is invalid
+#[derive(Default)]
+struct ConfigErrors(Vec<String>);
+
+impl ConfigErrors {
+    fn add(&mut self, path: &str, reason: impl Display) {
+        self.0.push(format!("{path}: {reason}"));
+    }
+
+    fn into_result(self) -> Result<()> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!("Invalid config:\n  {}", self.0.join("\n  ")))
+        }
+    }
+}
+
+// checks the settings make sense on their own, without connecting to anything
+pub fn validate_config(config: &WatchtowerConfig) -> Result<()> {
+    let mut errors = ConfigErrors::default();
+
+    validate_endpoints(
+        &mut errors,
+        "fuel_graphql",
+        &config.fuel_graphql,
+        config.fuel_graphql_quorum,
+        "fuel_graphql_quorum",
+    );
+    validate_endpoints(
+        &mut errors,
+        "ethereum_rpc",
+        &config.ethereum_rpc,
+        config.ethereum_rpc_quorum,
+        "ethereum_rpc_quorum",
+    );
+    if config.ethereum_rpc_timeout == 0 {
+        errors.add("ethereum_rpc_timeout", "must be greater than 0");
+    }
+    if let Some(ethereum_ws) = &config.ethereum_ws {
+        validate_url(&mut errors, "ethereum_ws", ethereum_ws, &["ws", "wss"]);
+    }
+    validate_hex(
+        &mut errors,
+        "state_contract_address",
+        &config.state_contract_address,
+        20,
+    );
+    validate_hex(
+        &mut errors,
+        "portal_contract_address",
+        &config.portal_contract_address,
+        20,
+    );
+    validate_hex(
+        &mut errors,
+        "gateway_contract_address",
+        &config.gateway_contract_address,
+        20,
+    );
+    for (i, ethereum_wallet_key) in config.ethereum_wallet_key.iter().enumerate() {
+        // the key itself is never included in the error
+        let path = format!("ethereum_wallet_key[{i}]");
+        if !is_hex(ethereum_wallet_key, 32) {
+            errors.add(&path, "must be a 32 byte hex private key");
+        }
+        if config.ethereum_wallet_key[..i].contains(ethereum_wallet_key) {
+            errors.add(&path, "is listed more than once");
+        }
+    }
+    let signers = [
+        !config.ethereum_wallet_key.is_empty(),
+        !config.ethereum_keystore.is_empty(),
+        !config.ethereum_remote_signer.is_empty(),
+    ];
+    if signers.iter().filter(|configured| **configured).count() > 1 {
+        errors.add(
+            "ethereum_wallet_key",
+            "only one of ethereum_wallet_key, ethereum_keystore and ethereum_remote_signer can be set",
+        );
+    }
+    for (i, keystore) in config.ethereum_keystore.iter().enumerate() {
+        if keystore.path.is_empty() {
+            errors.add(&format!("ethereum_keystore[{i}].path"), "must not be empty");
+        }
+        if matches!(&keystore.password_file, Some(password_file) if password_file.is_empty()) {
+            errors.add(&format!("ethereum_keystore[{i}].password_file"), "must not be empty");
+        }
+    }
+    for (i, remote_signer) in config.ethereum_remote_signer.iter().enumerate() {
+        let path = format!("ethereum_remote_signer[{i}]");
+        validate_url(
+            &mut errors,
+            &format!("{path}.url"),
+            &remote_signer.url,
+            &["http", "https"],
+        );
+        validate_hex(&mut errors, &format!("{path}.address"), &remote_signer.address, 20);
+        if remote_signer.timeout == 0 {
+            errors.add(&format!("{path}.timeout"), "must be greater than 0");
+        }
+        let address = remote_signer.address.to_lowercase();
+        if config.ethereum_remote_signer[..i]
+            .iter()
+            .any(|other| other.address.to_lowercase() == address)
+        {
+            errors.add(&format!("{path}.address"), "is listed more than once");
+        }
+    }
+    if let Some(safe) = &config.ethereum_safe {
+        validate_hex(&mut errors, "ethereum_safe.address", &safe.address, 20);
+        validate_url(
+            &mut errors,
+            "ethereum_safe.transaction_service_url",
+            &safe.transaction_service_url,
+            &["http", "https"],
+        );
+        if safe.timeout == 0 {
+            errors.add("ethereum_safe.timeout", "must be greater than 0");
+        }
+        if !config.has_signer() {
+            errors.add(
+                "ethereum_safe",
+                "needs the ethereum account of one of the Safe owners to sign proposals",
+            );
+        }
+    }
+    if let Some(private_relay) = &config.ethereum_private_relay {
+        validate_url(
+            &mut errors,
+            "ethereum_private_relay.url",
+            &private_relay.url,
+            &["http", "https"],
+        );
+        if private_relay.fallback_blocks == 0 {
+            errors.add("ethereum_private_relay.fallback_blocks", "must be greater than 0");
+        }
+        if private_relay.timeout == 0 {
+            errors.add("ethereum_private_relay.timeout", "must be greater than 0");
+        }
+        if config.ethereum_safe.is_some() {
+            errors.add(
+                "ethereum_private_relay",
+                "is never used when pauses are proposed to ethereum_safe",
+            );
+        }
+    }
+    if let Some(state_file) = &config.state_file {
+        if state_file.is_empty() {
+            errors.add("state_file", "must not be empty");
+        }
+    }
+    if let Some(action_log_file) = &config.action_log_file {
+        if action_log_file.is_empty() {
+            errors.add("action_log_file", "must not be empty");
+        }
+    }
+    if let Some(heartbeat) = &config.heartbeat {
+        validate_url(&mut errors, "heartbeat.url", &heartbeat.url, &["http", "https"]);
+        if heartbeat.interval == 0 {
+            errors.add("heartbeat.interval", "must be greater than 0");
+        }
+    }
+    if let Some(http_server) = &config.http_server {
+        if let Err(e) = http_server.address.parse::<SocketAddr>() {
+            errors.add("http_server.address", format!("must be an ip address and port ({e})"));
+        }
+        if matches!(&http_server.admin_token, Some(admin_token) if admin_token.is_empty()) {
+            errors.add("http_server.admin_token", "must not be empty");
+        }
+    }
+
+    // fuel alerts
+    let fuel = &config.fuel_client_watcher;
+    validate_generic_alert(
+        &mut errors,
+        "fuel_client_watcher.connection_alert",
+        &fuel.connection_alert,
+    );
+    validate_block_production_alert(
+        &mut errors,
+        "fuel_client_watcher.block_production_alert",
+        &fuel.block_production_alert,
+    );
+    validate_generic_alert(
+        &mut errors,
+        "fuel_client_watcher.endpoint_consensus_alert",
+        &fuel.endpoint_consensus_alert,
+    );
+    let mut rules = HashMap::new();
+    for (i, alert) in fuel.portal_withdraw_alerts.iter().enumerate() {
+        let path = format!("fuel_client_watcher.portal_withdraw_alerts[{i}]");
+        validate_withdraw_alert(&mut errors, &path, alert, false);
+        validate_unique(&mut errors, &mut rules, &path, format!("{}", alert.time_frame));
+    }
+    let mut rules = HashMap::new();
+    for (i, alert) in fuel.gateway_withdraw_alerts.iter().enumerate() {
+        let path = format!("fuel_client_watcher.gateway_withdraw_alerts[{i}]");
+        validate_withdraw_alert(&mut errors, &path, alert, true);
+        validate_unique(
+            &mut errors,
+            &mut rules,
+            &path,
+            format!("{} {}", alert.token_name, alert.time_frame),
+        );
+    }
+
+    // ethereum alerts
+    let ethereum = &config.ethereum_client_watcher;
+    validate_generic_alert(
+        &mut errors,
+        "ethereum_client_watcher.connection_alert",
+        &ethereum.connection_alert,
+    );
+    validate_block_production_alert(
+        &mut errors,
+        "ethereum_client_watcher.block_production_alert",
+        &ethereum.block_production_alert,
+    );
+    validate_account_funds_alert(
+        &mut errors,
+        "ethereum_client_watcher.account_funds_alert",
+        &ethereum.account_funds_alert,
+    );
+    validate_generic_alert(
+        &mut errors,
+        "ethereum_client_watcher.invalid_state_commit_alert",
+        &ethereum.invalid_state_commit_alert,
+    );
+    validate_generic_alert(
+        &mut errors,
+        "ethereum_client_watcher.endpoint_consensus_alert",
+        &ethereum.endpoint_consensus_alert,
+    );
+    let mut rules = HashMap::new();
+    for (i, alert) in ethereum.portal_deposit_alerts.iter().enumerate() {
+        let path = format!("ethereum_client_watcher.portal_deposit_alerts[{i}]");
+        validate_deposit_alert(&mut errors, &path, alert, false);
+        validate_unique(&mut errors, &mut rules, &path, format!("{}", alert.time_frame));
+    }
+    let mut rules = HashMap::new();
+    for (i, alert) in ethereum.gateway_deposit_alerts.iter().enumerate() {
+        let path = format!("ethereum_client_watcher.gateway_deposit_alerts[{i}]");
+        validate_deposit_alert(&mut errors, &path, alert, true);
+        validate_unique(
+            &mut errors,
+            &mut rules,
+            &path,
+            format!("{} {}", alert.token_name, alert.time_frame),
+        );
+    }
+
+    errors.into_result()
+}
+
+// checks the networks watched by one process can run side by side
+pub fn validate_networks(configs: &[WatchtowerConfig]) -> Result<()> {
+    let mut errors = ConfigErrors::default();
+
+    let mut state_files = HashMap::new();
+    let mut action_log_files = HashMap::new();
+    for config in configs {
+        let path = format!("networks.{}", config.network_name());
+        if let Some(network) = &config.network {
+            if network.is_empty()
+                || network
+                    .chars()
+                    .any(|c| !c.is_ascii_alphanumeric() && c != '_' && c != '-')
+            {
+                errors.add(&path, "network names may only contain letters, digits, '_' and '-'");
+            }
+        }
+
+        // progress is kept per network
+        if let Some(state_file) = &config.state_file {
+            match state_files.get(state_file) {
+                Some(other) => errors.add(
+                    &format!("{path}.state_file"),
+                    format!("is also used by network {other}, each network needs its own state file"),
+                ),
+                None => {
+                    state_files.insert(state_file.clone(), config.network_name().to_string());
+                }
+            }
+        }
+        if let Some(action_log_file) = &config.action_log_file {
+            match action_log_files.get(action_log_file) {
+                Some(other) => errors.add(
+                    &format!("{path}.action_log_file"),
+                    format!("is also used by network {other}, each network needs its own action log file"),
+                ),
+                None => {
+                    action_log_files.insert(action_log_file.clone(), config.network_name().to_string());
+                }
+            }
+        }
+
+        // a single server is shared by all networks
+        let http_server = config
+            .http_server
+            .as_ref()
+            .map(|http_server| (&http_server.address, &http_server.admin_token));
+        let first_http_server = configs[0]
+            .http_server
+            .as_ref()
+            .map(|http_server| (&http_server.address, &http_server.admin_token));
+        if http_server != first_http_server {
+            errors.add(
+                &format!("{path}.http_server"),
+                "must be the same for every network (set it outside of networks)",
+            );
+        }
+    }
+
+    errors.into_result()
+}
+
+// checks the config against the live endpoints: every endpoint is reachable and on the same chain, there is contract
+// code at every ethereum contract address, and the account actions are sent from can be unlocked
+pub async fn validate_config_live(config: &WatchtowerConfig) -> Result<()> {
+    let mut errors = ConfigErrors::default();
+
+    // fuel endpoints all serve the same chain
+    let mut fuel_chain: Option<(String, String)> = None;
+    for (i, url) in config.fuel_graphql.iter().enumerate() {
+        let path = format!("fuel_graphql[{i}]");
+        let chain_info = match FuelProvider::connect(url).await {
+            Ok(provider) => live_check(provider.chain_info()).await,
+            Err(e) => Err(e.to_string()),
+        };
+        match chain_info {
+            Ok(chain_info) => match &fuel_chain {
+                Some((first_path, name)) if *name != chain_info.name => errors.add(
+                    &path,
+                    format!(
+                        "serves chain {} but {} serves chain {}",
+                        chain_info.name, first_path, name
+                    ),
+                ),
+                Some(_) => {}
+                None => fuel_chain = Some((path, chain_info.name)),
+            },
+            Err(e) => errors.add(&path, format!("failed to connect ({e})")),
+        }
+    }
+
+    // ethereum endpoints all serve the same chain
+    let mut ethereum_chain: Option<(String, u64)> = None;
+    let mut ethereum_provider: Option<Provider<Http>> = None;
+    for (i, url) in config.ethereum_rpc.iter().enumerate() {
+        let path = format!("ethereum_rpc[{i}]");
+        let provider = match Provider::<Http>::try_from(url.as_str()) {
+            Ok(provider) => provider,
+            Err(e) => {
+                errors.add(&path, format!("invalid url ({e})"));
+                continue;
+            }
+        };
+        match live_check(provider.get_chainid()).await {
+            Ok(chain_id) => {
+                check_chain_id(&mut errors, &mut ethereum_chain, path, chain_id.as_u64());
+                ethereum_provider.get_or_insert(provider);
+            }
+            Err(e) => errors.add(&path, format!("failed to connect ({e})")),
+        }
+    }
+    if let Some(url) = &config.ethereum_ws {
+        let chain_id = match live_check(Provider::<Ws>::connect(url.as_str())).await {
+            Ok(provider) => live_check(provider.get_chainid()).await,
+            Err(e) => Err(e),
+        };
+        match chain_id {
+            Ok(chain_id) => check_chain_id(
+                &mut errors,
+                &mut ethereum_chain,
+                String::from("ethereum_ws"),
+                chain_id.as_u64(),
+            ),
+            Err(e) => errors.add("ethereum_ws", format!("failed to connect ({e})")),
+        }
+    }
+
+    // contracts are deployed on that chain
+    if let Some(provider) = ethereum_provider {
+        let mut contracts = vec![
+            (
+                String::from("state_contract_address"),
+                config.state_contract_address.clone(),
+            ),
+            (
+                String::from("portal_contract_address"),
+                config.portal_contract_address.clone(),
+            ),
+            (
+                String::from("gateway_contract_address"),
+                config.gateway_contract_address.clone(),
+            ),
+        ];
+        if let Some(safe) = &config.ethereum_safe {
+            contracts.push((String::from("ethereum_safe.address"), safe.address.clone()));
+        }
+        for (i, alert) in config.ethereum_client_watcher.gateway_deposit_alerts.iter().enumerate() {
+            contracts.push((
+                format!("ethereum_client_watcher.gateway_deposit_alerts[{i}].token_address"),
+                alert.token_address.clone(),
+            ));
+        }
+        for (path, address) in contracts {
+            let address = match address.parse::<H160>() {
+                Ok(address) => address,
+                Err(_) => continue,
+            };
+            match live_check(provider.get_code(address, None)).await {
+                Ok(code) if code.is_empty() => errors.add(&path, "no contract is deployed at this address"),
+                Ok(_) => {}
+                Err(e) => errors.add(&path, format!("failed to get contract code ({e})")),
+            }
+        }
+    }
+
+    // every keystore decrypts, and every remote signer holds the key of its address
+    for (i, keystore) in config.ethereum_keystore.iter().enumerate() {
+        if let Err(e) = WatchtowerSigner::from_keystore(keystore) {
+            errors.add(
+                &format!("ethereum_keystore[{i}]"),
+                format!("failed to set up the account ({e})"),
+            );
+        }
+    }
+    for (i, remote_signer) in config.ethereum_remote_signer.iter().enumerate() {
+        if let Err(e) = live_check(WatchtowerSigner::remote(remote_signer)).await {
+            errors.add(
+                &format!("ethereum_remote_signer[{i}]"),
+                format!("failed to set up the account ({e})"),
+            );
+        }
+    }
+
+    // the Safe transaction service knows the Safe
+    if let Some(safe) = &config.ethereum_safe {
+        if let Ok(address) = safe.address.parse::<H160>() {
+            let url = format!(
+                "{}/api/v1/safes/{}/",
+                safe.transaction_service_url.trim_end_matches('/'),
+                to_checksum(&address, None)
+            );
+            let response = live_check(Client::new().get(url).send()).await;
+            if let Err(e) = response.and_then(|response| response.error_for_status().map_err(|e| e.to_string())) {
+                errors.add(
+                    "ethereum_safe.transaction_service_url",
+                    format!("failed to look up the Safe ({e})"),
+                );
+            }
+        }
+    }
+
+    errors.into_result()
+}
+
+fn validate_endpoints(errors: &mut ConfigErrors, path: &str, urls: &[String], quorum: usize, quorum_path: &str) {
+    if urls.is_empty() {
+        errors.add(path, "must list at least one endpoint");
+    }
+    for (i, url) in urls.iter().enumerate() {
+        validate_url(errors, &format!("{path}[{i}]"), url, &["http", "https"]);
+        if urls[..i].contains(url) {
+            errors.add(&format!("{path}[{i}]"), "is listed more than once");
+        }
+    }
+    if quorum == 0 || quorum > urls.len() {
+        errors.add(
+            quorum_path,
+            format!("must be between 1 and the number of endpoints ({})", urls.len()),
+        );
+    }
+}
+
+fn validate_url(errors: &mut ConfigErrors, path: &str, url: &str, schemes: &[&str]) {
+    match Url::parse(url) {
+        Ok(parsed) => {
+            if !schemes.contains(&parsed.scheme()) {
+                errors.add(path, format!("must be a {} url", schemes.join(" or ")));
+            }
+        }
+        Err(e) => errors.add(path, format!("must be a valid url ({e})")),
+    }
+}
+
+fn validate_hex(errors: &mut ConfigErrors, path: &str, value: &str, bytes: usize) {
+    if !is_hex(value, bytes) {
+        errors.add(path, format!("must be a {bytes} byte hex string (got \"{value}\")"));
+    }
+}
+
+fn is_hex(value: &str, bytes: usize) -> bool {
+    let digits = value.strip_prefix("0x").unwrap_or(value);
+    digits.len() == bytes * 2 && digits.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn validate_schedule(errors: &mut ConfigErrors, path: &str, check_interval: Option<u64>, check_timeout: Option<u64>) {
+    if check_interval == Some(0) {
+        errors.add(&format!("{path}.check_interval"), "must be greater than 0");
+    }
+    if check_timeout == Some(0) {
+        errors.add(&format!("{path}.check_timeout"), "must be greater than 0");
+    }
+}
+
+fn validate_amount(errors: &mut ConfigErrors, path: &str, amount: f64) {
+    if !amount.is_finite() || amount <= 0.0 {
+        errors.add(path, format!("must be a number greater than 0 (got {amount})"));
+    }
+}
+
+fn validate_generic_alert(errors: &mut ConfigErrors, path: &str, alert: &GenericAlert) {
+    validate_schedule(errors, path, alert.check_interval, alert.check_timeout);
+}
+
+fn validate_block_production_alert(errors: &mut ConfigErrors, path: &str, alert: &BlockProductionAlert) {
+    validate_schedule(errors, path, alert.check_interval, alert.check_timeout);
+    if alert.max_block_time == 0 {
+        errors.add(&format!("{path}.max_block_time"), "must be greater than 0");
+    }
+}
+
+fn validate_account_funds_alert(errors: &mut ConfigErrors, path: &str, alert: &AccountFundsAlert) {
+    validate_schedule(errors, path, alert.check_interval, alert.check_timeout);
+    if !alert.min_balance.is_finite() || alert.min_balance < 0.0 {
+        errors.add(
+            &format!("{path}.min_balance"),
+            format!("must be a number of at least 0 (got {})", alert.min_balance),
+        );
+    }
+}
+
+fn validate_deposit_alert(errors: &mut ConfigErrors, path: &str, alert: &DepositAlert, is_token: bool) {
+    validate_schedule(errors, path, alert.check_interval, alert.check_timeout);
+    if alert.time_frame == 0 {
+        errors.add(&format!("{path}.time_frame"), "must be greater than 0");
+    }
+    validate_amount(errors, &format!("{path}.amount"), alert.amount);
+    if is_token {
+        validate_hex(errors, &format!("{path}.token_address"), &alert.token_address, 20);
+        if alert.token_name.is_empty() {
+            errors.add(&format!("{path}.token_name"), "must not be empty");
+        }
+    }
+}
+
+fn validate_withdraw_alert(errors: &mut ConfigErrors, path: &str, alert: &WithdrawAlert, is_token: bool) {
+    validate_schedule(errors, path, alert.check_interval, alert.check_timeout);
+    if alert.time_frame == 0 {
+        errors.add(&format!("{path}.time_frame"), "must be greater than 0");
+    }
+    validate_amount(errors, &format!("{path}.amount"), alert.amount);
+    if is_token {
+        validate_hex(errors, &format!("{path}.token_address"), &alert.token_address, 32);
+        if alert.token_name.is_empty() {
+            errors.add(&format!("{path}.token_name"), "must not be empty");
+        }
+    }
+}
+
+// rules for the same token and time frame would run the same check twice under the same name
+fn validate_unique(errors: &mut ConfigErrors, rules: &mut HashMap<String, String>, path: &str, rule: String) {
+    match rules.get(&rule) {
+        Some(first_path) => errors.add(path, format!("duplicates {first_path} (same token and time frame)")),
+        None => {
+            rules.insert(rule, path.to_string());
+        }
+    }
+}
+
+fn check_chain_id(errors: &mut ConfigErrors, first: &mut Option<(String, u64)>, path: String, chain_id: u64) {
+    match first {
+        Some((first_path, first_chain_id)) if *first_chain_id != chain_id => errors.add(
+            &path,
+            format!("is on chain id {chain_id} but {first_path} is on chain id {first_chain_id}"),
+        ),
+        Some(_) => {}
+        None => *first = Some((path, chain_id)),
+    }
+}
+
+async fn live_check<T, E: Display>(request: impl Future<Output = Result<T, E>>) -> Result<T, String> {
+    match tokio::time::timeout(LIVE_CHECK_TIMEOUT, request).await {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(format!("timed out after {} seconds", LIVE_CHECK_TIMEOUT.as_secs())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::HttpServerConfig;
+    use crate::test_utils::test_config;
+
+    fn network(name: &str, http_server_address: &str) -> WatchtowerConfig {
+        let mut config = test_config();
+        config.network = Some(name.to_string());
+        config.state_file = Some(format!("{name}_state.json"));
+        config.http_server = Some(HttpServerConfig {
+            address: http_server_address.to_string(),
+            admin_token: None,
+        });
+        config
+    }
+
+    #[test]
+    fn networks_share_one_http_server() {
+        let configs = [network("mainnet", "0.0.0.0:8080"), network("testnet", "0.0.0.0:8080")];
+        assert!(validate_networks(&configs).is_ok());
+
+        let configs = [network("mainnet", "0.0.0.0:8080"), network("testnet", "0.0.0.0:8081")];
+        let error = validate_networks(&configs).unwrap_err().to_string();
+        assert!(error.contains("networks.testnet.http_server"));
+
+        let mut configs = [network("mainnet", "0.0.0.0:8080"), network("testnet", "0.0.0.0:8080")];
+        configs[1].http_server = None;
+        assert!(validate_networks(&configs).is_err());
+    }
+
+    #[test]
+    fn networks_need_their_own_state_file() {
+        let mut configs = [network("mainnet", "0.0.0.0:8080"), network("testnet", "0.0.0.0:8080")];
+        configs[1].state_file = configs[0].state_file.clone();
+        let error = validate_networks(&configs).unwrap_err().to_string();
+        assert!(error.contains("networks.testnet.state_file"));
+    }
+}
@@ -0,0 +1,88 @@
+use crate::admin::ReloadRequest;
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+pub static CONFIG_POLL_INTERVAL: Duration = Duration::from_millis(5000);
+
+// asks for the config to be reloaded whenever one of the config files is modified or the process receives SIGHUP
+pub fn start_config_watcher(config_files: &[String], reload_sender: UnboundedSender<ReloadRequest>) -> JoinHandle<()> {
+    let config_files: Vec<PathBuf> = config_files.iter().map(PathBuf::from).collect();
+    tokio::spawn(async move {
+        let mut last_modified = get_modified_times(&config_files);
+        let mut hangup = hangup_signal();
+        loop {
+            let reason = tokio::select! {
+                _ = tokio::time::sleep(CONFIG_POLL_INTERVAL) => {
+                    let modified = get_modified_times(&config_files);
+                    if modified == last_modified {
+                        continue;
+                    }
+                    last_modified = modified;
+                    "config file changed"
+                }
+                _ = hangup.recv() => "received SIGHUP",
+            };
+
+            log::info!("Reloading config ({}).", reason);
+            let (reloaded, _) = oneshot::channel();
+            if reload_sender.send(reloaded).is_err() {
+                return;
+            }
+        }
+    })
+}
+
+fn get_modified_times(config_files: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    config_files
+        .iter()
+        .map(|config_file| fs::metadata(config_file).and_then(|metadata| metadata.modified()).ok())
+        .collect()
+}
+
+#[cfg(unix)]
+struct HangupSignal(Option<tokio::signal::unix::Signal>);
+
+#[cfg(unix)]
+impl HangupSignal {
+    async fn recv(&mut self) {
+        if let Some(signal) = &mut self.0 {
+            if signal.recv().await.is_some() {
+                return;
+            }
+        }
+        std::future::pending().await
+    }
+}
+
+#[cfg(unix)]
+fn hangup_signal() -> HangupSignal {
+    use tokio::signal::unix::{signal, SignalKind};
+    match signal(SignalKind::hangup()) {
+        Ok(signal) => HangupSignal(Some(signal)),
+        Err(e) => {
+            log::error!("Failed to listen for SIGHUP: {}", e);
+            HangupSignal(None)
+        }
+    }
+}
+
+// there is no SIGHUP outside of unix, so only file changes trigger a reload
+#[cfg(not(unix))]
+struct HangupSignal;
+
+#[cfg(not(unix))]
+impl HangupSignal {
+    async fn recv(&mut self) {
+        std::future::pending().await
+    }
+}
+
+#[cfg(not(unix))]
+fn hangup_signal() -> HangupSignal {
+    HangupSignal
+}
@@ -1,17 +1,27 @@
+use crate::action_log::{unix_time, ActionRecord, ActionStatus, PauseAttempt, PauseRecord, WatchtowerActionLog};
 use crate::alerts::{AlertLevel, WatchtowerAlerts};
-use crate::config::WatchtowerConfig;
-use crate::ethereum_watcher::state_contract::StateContract;
-use crate::ethereum_watcher::gateway_contract::GatewayContract;
-use crate::ethereum_watcher::portal_contract::PortalContract;
+use crate::clients::WatchtowerClients;
+use crate::ethereum_watcher::contract_pauser::ContractPauser;
+use crate::ethereum_watcher::ethereum_chain::EthereumChain;
+use crate::ethereum_watcher::safe_contract::SafeContract;
+use crate::metrics::{ActionResult, WatchtowerMetrics};
+use crate::WatchtowerConfig;
 
 use anyhow::Result;
-use ethers::providers::{Http, Middleware, Provider};
-use serde::Deserialize;
+use async_trait::async_trait;
+use ethers::abi::Abi;
+use ethers::types::{Address, Bytes, H256, U256};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
 use tokio::sync::mpsc::{self, UnboundedSender};
+use tokio::sync::oneshot;
 
 pub static THREAD_CONNECTIONS_ERR: &str = "Connections to the ethereum actions thread have all closed.";
+pub static SHUTDOWN_TIMEOUT: Duration = Duration::from_millis(180000);
 
-#[derive(Deserialize, Clone, PartialEq, Eq, Debug)]
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum EthereumAction {
     None,
     PauseState,
@@ -20,102 +30,174 @@ pub enum EthereumAction {
     PauseAll,
 }
 
+// contracts the actions thread is able to pause, from any of the accounts set up to pause them (in order of
+// preference). Sending and confirming a pause are kept apart so the transaction is known even if it never confirms
+#[async_trait]
+pub trait PausableContract: Send + Sync {
+    fn pauser_count(&self) -> usize;
+    fn get_pauser_address(&self, pauser: usize) -> Result<Address>;
+    fn get_pause_call(&self) -> Result<(Address, Bytes)>;
+    fn get_abi(&self) -> &Abi;
+    async fn check_pauser(&self, pauser: usize, min_balance: U256) -> Result<()>;
+    async fn send_pause(&self, pauser: usize) -> Result<H256>;
+    async fn confirm_pause(&self, tx_hash: H256) -> Result<Option<U256>>;
+    async fn is_paused(&self) -> Result<bool>;
+}
+
+#[async_trait]
+impl PausableContract for ContractPauser {
+    fn pauser_count(&self) -> usize {
+        ContractPauser::pauser_count(self)
+    }
+    fn get_pauser_address(&self, pauser: usize) -> Result<Address> {
+        ContractPauser::get_pauser_address(self, pauser)
+    }
+    fn get_pause_call(&self) -> Result<(Address, Bytes)> {
+        ContractPauser::get_pause_call(self)
+    }
+    fn get_abi(&self) -> &Abi {
+        ContractPauser::get_abi(self)
+    }
+    async fn check_pauser(&self, pauser: usize, min_balance: U256) -> Result<()> {
+        ContractPauser::check_pauser(self, pauser, min_balance).await
+    }
+    async fn send_pause(&self, pauser: usize) -> Result<H256> {
+        ContractPauser::send_pause(self, pauser).await
+    }
+    async fn confirm_pause(&self, tx_hash: H256) -> Result<Option<U256>> {
+        ContractPauser::confirm_pause(self, tx_hash)
+            .await
+            .map(|receipt| receipt.gas_used)
+    }
+    async fn is_paused(&self) -> Result<bool> {
+        ContractPauser::is_paused(self).await
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct WatchtowerEthereumActions {
-    action_sender: UnboundedSender<ActionParams>,
+    action_sender: UnboundedSender<ActionMessage>,
+    pending_actions: Arc<Mutex<HashSet<EthereumAction>>>,
+    alerts: WatchtowerAlerts,
+    action_log: WatchtowerActionLog,
+    network: String,
+    check: Option<String>,
 }
 
 impl WatchtowerEthereumActions {
-    pub async fn new(config: &WatchtowerConfig, alerts: WatchtowerAlerts) -> Result<Self> {
-        // setup provider and check that it is valid
-        let provider = Provider::<Http>::try_from(&config.ethereum_rpc)?;
-        let provider_result = provider.get_chainid().await;
-        match provider_result {
-            Err(_) => return Err(anyhow::anyhow!("Invalid ethereum RPC.")),
-            _ => {}
-        }
-
-        // setup contracts
-        let state_contract = StateContract::new(config).await?;
-        let gateway_contract = GatewayContract::new(config).await?;
-        let portal_contract = PortalContract::new(config).await?;
+    pub async fn new(
+        config: &WatchtowerConfig,
+        clients: &WatchtowerClients,
+        alerts: WatchtowerAlerts,
+        metrics: WatchtowerMetrics,
+        dry_run: bool,
+    ) -> Result<Self> {
+        // pause accounts below the funds alert threshold are only used once the others have failed
+        let min_balance = EthereumChain::get_value(config.ethereum_client_watcher.account_funds_alert.min_balance, 18);
+        Ok(WatchtowerEthereumActions::start(
+            Arc::new(clients.state_contract.get_pauser().clone()),
+            Arc::new(clients.gateway_contract.get_pauser().clone()),
+            Arc::new(clients.portal_contract.get_pauser().clone()),
+            clients.safe_contract.clone().map(Arc::new),
+            min_balance,
+            config.network_name().to_string(),
+            WatchtowerActionLog::new(config),
+            alerts,
+            metrics,
+            dry_run,
+        ))
+    }
 
+    #[allow(clippy::too_many_arguments)]
+    pub fn start(
+        state_contract: Arc<dyn PausableContract>,
+        gateway_contract: Arc<dyn PausableContract>,
+        portal_contract: Arc<dyn PausableContract>,
+        safe_contract: Option<Arc<SafeContract>>,
+        min_balance: U256,
+        network: String,
+        action_log: WatchtowerActionLog,
+        alerts: WatchtowerAlerts,
+        metrics: WatchtowerMetrics,
+        dry_run: bool,
+    ) -> Self {
         // start handler thread for action function
-        let (tx, mut rx) = mpsc::unbounded_channel::<ActionParams>();
+        let (tx, mut rx) = mpsc::unbounded_channel::<ActionMessage>();
+        let pending_actions = Arc::new(Mutex::new(HashSet::new()));
+        let actions = WatchtowerEthereumActions {
+            action_sender: tx,
+            pending_actions: pending_actions.clone(),
+            alerts: alerts.clone(),
+            action_log: action_log.clone(),
+            network: network.clone(),
+            check: None,
+        };
         tokio::spawn(async move {
             loop {
                 let received_result = rx.recv().await;
                 match received_result {
-                    Some(params) => {
-                        match params.action {
-                            EthereumAction::PauseState => {
-                                alerts.alert(String::from("Pausing state contract."), AlertLevel::Info);
-                                match state_contract.pause().await {
-                                    Err(e) => alerts.alert(e.to_string(), params.alert_level),
-                                    Ok(_) => {
-                                        alerts.alert(
-                                            String::from("Successfully paused state contract."),
-                                            AlertLevel::Info,
-                                        );
-                                    }
-                                }
-                            }
-                            EthereumAction::PauseGateway => {
-                                alerts.alert(String::from("Pausing gateway contract."), AlertLevel::Info);
-                                match gateway_contract.pause().await {
-                                    Err(e) => alerts.alert(e.to_string(), params.alert_level),
-                                    Ok(_) => {
-                                        alerts.alert(
-                                            String::from("Successfully paused gateway contract."),
-                                            AlertLevel::Info,
-                                        );
-                                    }
-                                }
-                            }
-                            EthereumAction::PausePortal => {
-                                alerts.alert(String::from("Pausing portal contract."), AlertLevel::Info);
-                                match portal_contract.pause().await {
-                                    Err(e) => alerts.alert(e.to_string(), params.alert_level),
-                                    Ok(_) => {
-                                        alerts.alert(
-                                            String::from("Successfully paused portal contract."),
-                                            AlertLevel::Info,
-                                        );
-                                    }
+                    Some(ActionMessage::Shutdown(drained)) => {
+                        // every action sent before the shutdown request has finished by now
+                        let _ = drained.send(());
+                        return;
+                    }
+                    Some(ActionMessage::Action(params)) if params.action == EthereumAction::None => {}
+                    Some(ActionMessage::Action(params)) => {
+                        // keep a record of the decision, from the check that asked for it to the transactions sent
+                        let action = params.action.clone();
+                        let mut progress = ActionProgress::start(
+                            ActionRecord {
+                                network: network.clone(),
+                                action: params.action,
+                                check: params.check,
+                                alert: params.alert,
+                                alert_level: format!("{:?}", params.alert_level),
+                                requested_at: unix_time(params.requested_at),
+                                started_at: unix_time(SystemTime::now()),
+                                finished_at: None,
+                                status: ActionStatus::Started,
+                                pauses: vec![],
+                            },
+                            &action_log,
+                            &alerts,
+                        );
+                        let status = if dry_run {
+                            alerts.alert(format!("Dry run, skipped action: {:?}", action), AlertLevel::Info);
+                            ActionStatus::Skipped
+                        } else {
+                            let contracts: Vec<(&str, &dyn PausableContract)> = match action {
+                                EthereumAction::PauseState => vec![("state", &*state_contract)],
+                                EthereumAction::PauseGateway => vec![("gateway", &*gateway_contract)],
+                                EthereumAction::PausePortal => vec![("portal", &*portal_contract)],
+                                EthereumAction::PauseAll => {
+                                    alerts.alert(String::from("Pausing all contracts."), AlertLevel::Info);
+                                    vec![
+                                        ("state", &*state_contract),
+                                        ("gateway", &*gateway_contract),
+                                        ("portal", &*portal_contract),
+                                    ]
                                 }
+                                EthereumAction::None => vec![],
+                            };
+                            let level = &params.alert_level;
+                            let safe = safe_contract.as_deref();
+                            for (contract_name, contract) in contracts {
+                                let pause = pause(
+                                    contract_name,
+                                    contract,
+                                    safe,
+                                    min_balance,
+                                    &metrics,
+                                    level,
+                                    &mut progress,
+                                )
+                                .await;
+                                progress.finish_pause(pause);
                             }
-                            EthereumAction::PauseAll => {
-                                alerts.alert(String::from("Pausing all contracts."), AlertLevel::Info);
-                                match state_contract.pause().await {
-                                    Err(e) => alerts.alert(e.to_string(), params.alert_level.clone()),
-                                    Ok(_) => {
-                                        alerts.alert(
-                                            String::from("Successfully paused state contract."),
-                                            AlertLevel::Info,
-                                        );
-                                    }
-                                };
-                                match gateway_contract.pause().await {
-                                    Err(e) => alerts.alert(e.to_string(), params.alert_level.clone()),
-                                    Ok(_) => {
-                                        alerts.alert(
-                                            String::from("Successfully paused gateway contract."),
-                                            AlertLevel::Info,
-                                        );
-                                    }
-                                };
-                                match portal_contract.pause().await {
-                                    Err(e) => alerts.alert(e.to_string(), params.alert_level.clone()),
-                                    Ok(_) => {
-                                        alerts.alert(
-                                            String::from("Successfully paused portal contract."),
-                                            AlertLevel::Info,
-                                        );
-                                    }
-                                };
-                            }
-                            EthereumAction::None => {}
+                            get_action_status(&progress.record.pauses)
                         };
+                        progress.finish(status);
+                        pending_actions.lock().unwrap().remove(&action);
                     }
                     None => {
                         alerts.alert(String::from(THREAD_CONNECTIONS_ERR), AlertLevel::Error);
@@ -125,16 +207,381 @@ impl WatchtowerEthereumActions {
             }
         });
 
-        Ok(WatchtowerEthereumActions { action_sender: tx })
+        actions
     }
 
-    pub fn action(&self, action: EthereumAction, alert_level: Option<AlertLevel>) {
+    // actions requested through the returned copy are recorded in the action log as asked for by the given check
+    pub fn for_check(&self, check: &str) -> Self {
+        WatchtowerEthereumActions {
+            action_sender: self.action_sender.clone(),
+            pending_actions: self.pending_actions.clone(),
+            alerts: self.alerts.for_check(check),
+            action_log: self.action_log.clone(),
+            network: self.network.clone(),
+            check: Some(check.to_string()),
+        }
+    }
+
+    // returns whether the action was queued, which it is not when already pending
+    pub fn action(&self, action: EthereumAction, alert_level: Option<AlertLevel>) -> bool {
         let alert_level = match alert_level {
             Some(level) => level,
             None => AlertLevel::Info,
         };
-        let params = ActionParams { action, alert_level };
-        self.action_sender.send(params).unwrap();
+
+        // checks keep asking for the same action while their alert lasts, but one still queued or in progress (or a
+        // pending pause of every contract) already covers it
+        if action != EthereumAction::None {
+            let mut pending_actions = self.pending_actions.lock().unwrap();
+            if pending_actions.contains(&action) || pending_actions.contains(&EthereumAction::PauseAll) {
+                drop(pending_actions);
+                log::debug!("Dropped action {:?}, it is already pending.", action);
+                self.log_dropped(action, alert_level);
+                return false;
+            }
+            pending_actions.insert(action.clone());
+        }
+
+        // the alert the check raised along with the action, so the record shows why it was taken
+        let alert = self.get_alert();
+        let params = ActionParams {
+            action,
+            alert_level,
+            check: self.check.clone(),
+            alert,
+            requested_at: SystemTime::now(),
+        };
+        if let Err(e) = self.action_sender.send(ActionMessage::Action(params)) {
+            if let ActionMessage::Action(params) = e.0 {
+                log::error!("{} Dropped action: {:?}", THREAD_CONNECTIONS_ERR, params.action);
+                self.pending_actions.lock().unwrap().remove(&params.action);
+                return false;
+            }
+        }
+        true
+    }
+
+    // records an action that was not taken, so the action log shows every check that asked for it
+    fn log_dropped(&self, action: EthereumAction, alert_level: AlertLevel) {
+        let record = ActionRecord {
+            network: self.network.clone(),
+            action,
+            check: self.check.clone(),
+            alert: self.get_alert(),
+            alert_level: format!("{:?}", alert_level),
+            requested_at: unix_time(SystemTime::now()),
+            started_at: None,
+            finished_at: None,
+            status: ActionStatus::Dropped,
+            pauses: vec![],
+        };
+        if let Err(e) = self.action_log.append(&record) {
+            log::error!("Failed to write dropped action to the action log: {}", e);
+        }
+    }
+
+    fn get_alert(&self) -> Option<String> {
+        self.alerts.get_active_alert().map(|active_alert| active_alert.text)
+    }
+
+    // the latest records of the action log, oldest first
+    pub fn get_recent_actions(&self, limit: usize) -> Result<Vec<ActionRecord>> {
+        self.action_log.read_recent(limit)
+    }
+
+    // waits for in-flight and queued actions to finish (up to the shutdown timeout) and stops taking new ones
+    pub async fn shutdown(&self) -> Result<()> {
+        let (drained, wait_for_drain) = oneshot::channel();
+        if self.action_sender.send(ActionMessage::Shutdown(drained)).is_err() {
+            return Ok(());
+        }
+        match tokio::time::timeout(SHUTDOWN_TIMEOUT, wait_for_drain).await {
+            Err(_) => Err(anyhow::anyhow!(
+                "Timed out after {} seconds waiting for ethereum actions to finish.",
+                SHUTDOWN_TIMEOUT.as_secs()
+            )),
+            Ok(_) => Ok(()),
+        }
+    }
+}
+
+// pauses the contract from the configured accounts, or proposes the pause to the Safe if one is set to hold the
+// pauser role, unless it is paused already. Returns what was tried for the action log
+async fn pause(
+    contract_name: &str,
+    contract: &dyn PausableContract,
+    safe: Option<&SafeContract>,
+    min_balance: U256,
+    metrics: &WatchtowerMetrics,
+    alert_level: &AlertLevel,
+    progress: &mut ActionProgress<'_>,
+) -> PauseRecord {
+    let alerts = progress.alerts;
+    // failing to read the state should never hold up a pause
+    if let Ok(true) = contract.is_paused().await {
+        alerts.alert(
+            format!("The {contract_name} contract is already paused."),
+            AlertLevel::Info,
+        );
+        return PauseRecord {
+            contract: contract_name.to_string(),
+            status: ActionStatus::AlreadyPaused,
+            attempts: vec![],
+            error: None,
+        };
+    }
+
+    alerts.alert(format!("Pausing {contract_name} contract."), AlertLevel::Info);
+    metrics.record_action(contract_name, ActionResult::Attempted);
+    let mut attempts = vec![];
+    let result = match safe {
+        Some(safe) => propose_pause(contract_name, contract, safe, &mut attempts)
+            .await
+            .map(|safe_tx_hash| {
+                // the other owners have to act on it, so they are alerted at the level of the alert that asked for it
+                let message = format!(
+                    "Proposed pausing {} contract to Safe {:?}. Other Safe owners need to confirm and execute safe tx {:?}.",
+                    contract_name,
+                    safe.get_address(),
+                    safe_tx_hash
+                );
+                (message, alert_level.clone(), ActionStatus::Proposed)
+            }),
+        None => pause_with_failover(contract_name, contract, min_balance, progress, &mut attempts)
+            .await
+            .map(|_| {
+                (
+                    format!("Successfully paused {contract_name} contract."),
+                    AlertLevel::Info,
+                    ActionStatus::Succeeded,
+                )
+            }),
+    };
+    match result {
+        Err(e) => {
+            metrics.record_action(contract_name, ActionResult::Failed);
+            alerts.alert(e.to_string(), alert_level.clone());
+            PauseRecord {
+                contract: contract_name.to_string(),
+                status: ActionStatus::Failed,
+                attempts,
+                error: Some(e.to_string()),
+            }
+        }
+        Ok((message, level, status)) => {
+            metrics.record_action(contract_name, ActionResult::Succeeded);
+            alerts.alert(message, level);
+            PauseRecord {
+                contract: contract_name.to_string(),
+                status,
+                attempts,
+                error: None,
+            }
+        }
+    }
+}
+
+async fn propose_pause(
+    contract_name: &str,
+    contract: &dyn PausableContract,
+    safe: &SafeContract,
+    attempts: &mut Vec<PauseAttempt>,
+) -> Result<H256> {
+    let (to, data) = contract.get_pause_call()?;
+    let mut attempt = PauseAttempt {
+        safe: Some(safe.get_address()),
+        started_at: unix_time(SystemTime::now()),
+        ..Default::default()
+    };
+
+    // never ask the owners to execute a pause that is bound to revert
+    let result = match safe.simulate(to, data.clone(), contract.get_abi()).await {
+        Ok(_) => safe.propose(to, data).await,
+        Err(e) => Err(anyhow::anyhow!("Pausing would fail: {}", e)),
+    };
+    attempt.finished_at = unix_time(SystemTime::now());
+    match result {
+        Err(e) => {
+            attempt.error = Some(e.to_string());
+            attempts.push(attempt);
+            Err(anyhow::anyhow!(
+                "Failed to propose pausing {} contract: {}",
+                contract_name,
+                e
+            ))
+        }
+        Ok((safe_tx_hash, owner)) => {
+            attempt.account = owner;
+            attempt.safe_tx_hash = Some(safe_tx_hash);
+            attempts.push(attempt);
+            Ok(safe_tx_hash)
+        }
+    }
+}
+
+// sends the pause from the first healthy account, failing over to the next one if it fails or does not land in time.
+// Accounts that fail their health check are still tried, but only once every healthy account has failed
+async fn pause_with_failover(
+    contract_name: &str,
+    contract: &dyn PausableContract,
+    min_balance: U256,
+    progress: &mut ActionProgress<'_>,
+    attempts: &mut Vec<PauseAttempt>,
+) -> Result<()> {
+    let alerts = progress.alerts;
+    if contract.pauser_count() == 0 {
+        return Err(anyhow::anyhow!(
+            "Ethereum account not configured, the {} contract is read only.",
+            contract_name
+        ));
+    }
+
+    let mut unhealthy = vec![];
+    let mut errors = vec![];
+    for pauser in 0..contract.pauser_count() {
+        if let Err(e) = contract.check_pauser(pauser, min_balance).await {
+            alerts.alert(
+                format!("Skipping unhealthy pause account for the {contract_name} contract: {e}"),
+                AlertLevel::Warn,
+            );
+            unhealthy.push(pauser);
+            continue;
+        }
+        match send_pause(contract_name, contract, pauser, progress, attempts).await {
+            Ok(_) => return Ok(()),
+            Err(e) => errors.push(e),
+        }
+    }
+    for pauser in unhealthy {
+        match send_pause(contract_name, contract, pauser, progress, attempts).await {
+            Ok(_) => return Ok(()),
+            Err(e) => errors.push(e),
+        }
+    }
+    Err(anyhow::anyhow!(
+        "Failed to pause {} contract from every account: {}",
+        contract_name,
+        errors.join(" ")
+    ))
+}
+
+async fn send_pause(
+    contract_name: &str,
+    contract: &dyn PausableContract,
+    pauser: usize,
+    progress: &mut ActionProgress<'_>,
+    attempts: &mut Vec<PauseAttempt>,
+) -> Result<(), String> {
+    let alerts = progress.alerts;
+    let address = contract.get_pauser_address(pauser).map_err(|e| e.to_string())?;
+    let mut attempt = PauseAttempt {
+        account: Some(address),
+        started_at: unix_time(SystemTime::now()),
+        ..Default::default()
+    };
+    let result = match contract.send_pause(pauser).await {
+        Ok(tx_hash) => {
+            // written down before waiting on it, in case the watchtower never gets to see it confirmed
+            attempt.tx_hash = Some(tx_hash);
+            progress.sent(contract_name, &attempt);
+            contract.confirm_pause(tx_hash).await
+        }
+        Err(e) => Err(e),
+    };
+    attempt.finished_at = unix_time(SystemTime::now());
+    let result = match result {
+        Ok(gas_used) => {
+            attempt.gas_used = gas_used;
+            Ok(())
+        }
+        Err(e) => {
+            attempt.error = Some(e.to_string());
+
+            // a pause sent earlier that timed out may have landed in the meantime
+            if let Ok(true) = contract.is_paused().await {
+                Ok(())
+            } else {
+                alerts.alert(format!("Pause from {:?} failed: {}", address, e), AlertLevel::Warn);
+                Err(format!("{:?}: {}", address, e))
+            }
+        }
+    };
+    attempts.push(attempt);
+    result
+}
+
+// the record of an action as it goes, appended to the action log once decided on, once each pause transaction is
+// sent and once finished
+struct ActionProgress<'a> {
+    record: ActionRecord,
+    action_log: &'a WatchtowerActionLog,
+    alerts: &'a WatchtowerAlerts,
+}
+
+impl<'a> ActionProgress<'a> {
+    fn start(record: ActionRecord, action_log: &'a WatchtowerActionLog, alerts: &'a WatchtowerAlerts) -> Self {
+        let progress = ActionProgress {
+            record,
+            action_log,
+            alerts,
+        };
+        progress.append();
+        progress
+    }
+
+    // a pause transaction of the contract was sent, and is yet to be confirmed
+    fn sent(&mut self, contract_name: &str, attempt: &PauseAttempt) {
+        self.record.status = ActionStatus::Sent;
+        match self
+            .record
+            .pauses
+            .iter_mut()
+            .find(|pause| pause.contract == contract_name)
+        {
+            Some(pause) => pause.attempts.push(attempt.clone()),
+            None => self.record.pauses.push(PauseRecord {
+                contract: contract_name.to_string(),
+                status: ActionStatus::Sent,
+                attempts: vec![attempt.clone()],
+                error: None,
+            }),
+        }
+        self.append();
+    }
+
+    // replaces what was sent for the contract with how its pause ended
+    fn finish_pause(&mut self, pause: PauseRecord) {
+        self.record.pauses.retain(|sent| sent.contract != pause.contract);
+        self.record.pauses.push(pause);
+    }
+
+    fn finish(mut self, status: ActionStatus) {
+        self.record.status = status;
+        self.record.finished_at = unix_time(SystemTime::now());
+        self.append();
+    }
+
+    fn append(&self) {
+        if let Err(e) = self.action_log.append(&self.record) {
+            self.alerts.alert(
+                format!("Failed to write action to the action log: {e}"),
+                AlertLevel::Error,
+            );
+        }
+    }
+}
+
+// an action failed if any of its pauses did, is only proposed while a Safe still has to execute one of them, and had
+// nothing to do if every contract was paused already
+fn get_action_status(pauses: &[PauseRecord]) -> ActionStatus {
+    if pauses.iter().any(|pause| pause.status == ActionStatus::Failed) {
+        ActionStatus::Failed
+    } else if pauses.iter().any(|pause| pause.status == ActionStatus::Proposed) {
+        ActionStatus::Proposed
+    } else if pauses.iter().all(|pause| pause.status == ActionStatus::AlreadyPaused) {
+        ActionStatus::AlreadyPaused
+    } else {
+        ActionStatus::Succeeded
     }
 }
 
@@ -142,4 +589,168 @@ impl WatchtowerEthereumActions {
 struct ActionParams {
     action: EthereumAction,
     alert_level: AlertLevel,
+    check: Option<String>,
+    alert: Option<String>,
+    requested_at: SystemTime,
+}
+
+#[derive(Debug)]
+enum ActionMessage {
+    Action(ActionParams),
+    Shutdown(oneshot::Sender<()>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::{CheckSchedule, CheckScheduler};
+    use crate::status::WatchtowerStatus;
+    use crate::test_utils::{start_actions, test_config, TestContract};
+    use std::env;
+    use std::fs;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn actions_run_while_checks_wait() {
+        let paused = Arc::new(AtomicBool::new(false));
+        let contract = Arc::new(TestContract {
+            paused: paused.clone(),
+            pauses_land: true,
+            ..Default::default()
+        });
+        let (actions, alerts) = start_actions(contract);
+
+        // one check stuck on a slow request and one waiting between polls, on the same single threaded runtime
+        let mut scheduler = CheckScheduler::new("test", WatchtowerStatus::new(), alerts, actions.clone());
+        scheduler.schedule(
+            String::from("slow check"),
+            CheckSchedule::new(Some(1), Some(60), Duration::ZERO),
+            AlertLevel::None,
+            EthereumAction::None,
+            || async {
+                tokio::time::sleep(Duration::from_secs(30)).await;
+                Ok(())
+            },
+        );
+        scheduler.schedule(
+            String::from("waiting check"),
+            CheckSchedule::new(Some(30), Some(60), Duration::ZERO),
+            AlertLevel::None,
+            EthereumAction::None,
+            || async { Ok(()) },
+        );
+        tokio::spawn(scheduler.run());
+
+        actions.action(EthereumAction::PauseState, None);
+        let result = tokio::time::timeout(Duration::from_millis(500), async {
+            while !paused.load(Ordering::SeqCst) {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await;
+        assert!(result.is_ok(), "pause action was blocked by the scheduled checks");
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn paused_contracts_are_not_paused_again() {
+        let contract = Arc::new(TestContract {
+            paused: Arc::new(AtomicBool::new(true)),
+            ..Default::default()
+        });
+        let (actions, _) = start_actions(contract.clone());
+
+        actions.action(EthereumAction::PauseAll, None);
+        actions.shutdown().await.unwrap();
+        assert_eq!(contract.pauses_sent.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn pending_actions_are_not_queued_again() {
+        let contract = Arc::new(TestContract::default());
+        let (actions, _) = start_actions(contract.clone());
+
+        // the pause of every contract covers the others asked for while it is pending
+        assert!(actions.action(EthereumAction::PauseAll, None));
+        assert!(!actions.action(EthereumAction::PauseAll, None));
+        assert!(!actions.action(EthereumAction::PauseState, None));
+        assert!(!actions
+            .for_check("other check")
+            .action(EthereumAction::PausePortal, None));
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !actions.pending_actions.lock().unwrap().is_empty() {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(contract.pauses_sent.load(Ordering::SeqCst), 3);
+
+        // and once it has finished, the next one is taken
+        assert!(actions.action(EthereumAction::PauseState, None));
+        actions.shutdown().await.unwrap();
+        assert_eq!(contract.pauses_sent.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn actions_are_logged_as_they_go() {
+        let file_path = env::temp_dir().join(format!("watchtower_action_log_{}_actions", std::process::id()));
+        let _ = fs::remove_file(&file_path);
+        let mut config = test_config();
+        config.action_log_file = Some(file_path.to_string_lossy().to_string());
+        let metrics = WatchtowerMetrics::new();
+        let alerts = WatchtowerAlerts::new(&config, metrics.clone()).unwrap();
+        let contract = Arc::new(TestContract {
+            pauses_land: true,
+            ..Default::default()
+        });
+        let actions = WatchtowerEthereumActions::start(
+            contract.clone(),
+            contract.clone(),
+            contract,
+            None,
+            U256::zero(),
+            String::from("test"),
+            WatchtowerActionLog::new(&config),
+            alerts,
+            metrics,
+            false,
+        )
+        .for_check("test check");
+
+        assert!(actions.action(EthereumAction::PauseState, None));
+        assert!(!actions.action(EthereumAction::PauseState, None));
+        actions.shutdown().await.unwrap();
+
+        // the dropped action is written right away, the other once decided on, once sent and once finished
+        let records: Vec<ActionRecord> = fs::read_to_string(&file_path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let statuses: Vec<ActionStatus> = records.iter().map(|record| record.status.clone()).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ActionStatus::Dropped,
+                ActionStatus::Started,
+                ActionStatus::Sent,
+                ActionStatus::Succeeded
+            ]
+        );
+        assert_eq!(records[0].check, Some(String::from("test check")));
+        assert!(records[1].pauses.is_empty());
+        assert_eq!(records[2].pauses[0].status, ActionStatus::Sent);
+        assert_eq!(records[2].pauses[0].attempts[0].tx_hash, Some(H256::zero()));
+        assert_eq!(records[2].finished_at, None);
+        assert_eq!(records[3].pauses.len(), 1);
+        assert_eq!(records[3].pauses[0].status, ActionStatus::Succeeded);
+
+        let statuses: Vec<ActionStatus> = actions
+            .get_recent_actions(10)
+            .unwrap()
+            .into_iter()
+            .map(|record| record.status)
+            .collect();
+        assert_eq!(statuses, vec![ActionStatus::Dropped, ActionStatus::Succeeded]);
+    }
 }
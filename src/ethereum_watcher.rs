@@ -1,275 +1,586 @@
 use crate::alerts::{AlertLevel, WatchtowerAlerts};
-use crate::ethereum_actions::WatchtowerEthereumActions;
+use crate::clients::WatchtowerClients;
+use crate::config::{AccountFundsAlert, BlockProductionAlert, DepositAlert, GenericAlert};
+use crate::ethereum_actions::{EthereumAction, WatchtowerEthereumActions};
 use crate::fuel_watcher::fuel_chain::FuelChain;
+use crate::metrics::{Chain, Direction, WatchtowerMetrics};
+use crate::scheduler::{CheckSchedule, CheckScheduler};
+use crate::state::WatchtowerState;
+use crate::status::WatchtowerStatus;
 use crate::WatchtowerConfig;
 
 use anyhow::Result;
-use state_contract::StateContract;
 use ethereum_chain::EthereumChain;
+use ethereum_subscriber::EthereumSubscriber;
 use gateway_contract::GatewayContract;
 use portal_contract::PortalContract;
-use std::cmp::max;
-use std::thread;
+use state_contract::StateContract;
 use std::time::Duration;
 use tokio::task::JoinHandle;
 
-pub mod state_contract;
+pub mod contract_pauser;
 pub mod ethereum_chain;
+pub mod ethereum_client;
+pub mod ethereum_signer;
+pub mod ethereum_subscriber;
 pub mod gateway_contract;
 pub mod portal_contract;
+pub mod preflight;
+pub mod private_relay;
+pub mod safe_contract;
+pub mod state_contract;
 
 pub static POLL_DURATION: Duration = Duration::from_millis(6000);
-pub static POLL_LOGGING_SKIP: u64 = 50;
+pub static WEBSOCKET_POLL_DURATION: Duration = Duration::from_millis(60000);
+pub static WEBSOCKET_RECONNECT_DELAY: Duration = Duration::from_millis(1000);
+pub static WEBSOCKET_MAX_RECONNECT_DELAY: Duration = Duration::from_millis(60000);
+pub static POLL_LOGGING_SKIP: u32 = 50;
 pub static COMMIT_CHECK_STARTING_OFFSET: u64 = 24 * 60 * 60;
 pub static ETHEREUM_CONNECTION_RETRIES: u64 = 2;
-pub static ETHEREUM_BLOCK_TIME: u64 = 12;
+pub static TX_CONFIRMATION_TIMEOUT: Duration = Duration::from_millis(120000);
+
+// clients and services shared by the ethereum checks
+#[derive(Clone, Debug)]
+struct EthereumWatcher {
+    fuel_chain: FuelChain,
+    ethereum_chain: EthereumChain,
+    state_contract: StateContract,
+    gateway_contract: GatewayContract,
+    portal_contract: PortalContract,
+    alerts: WatchtowerAlerts,
+    actions: WatchtowerEthereumActions,
+    metrics: WatchtowerMetrics,
+    log_tag: String,
+}
+
+impl EthereumWatcher {
+    fn for_check(&self, check: &str) -> Self {
+        let mut watcher = self.clone();
+        watcher.alerts = self.alerts.for_check(check);
+        watcher.actions = self.actions.for_check(check);
+        watcher
+    }
+}
 
 pub async fn start_ethereum_watcher(
     config: &WatchtowerConfig,
+    clients: &WatchtowerClients,
+    state: &WatchtowerState,
+    status: &WatchtowerStatus,
+    metrics: &WatchtowerMetrics,
     actions: WatchtowerEthereumActions,
     alerts: WatchtowerAlerts,
 ) -> Result<JoinHandle<()>> {
-    let fuel_chain = FuelChain::new(config).await?;
-    let ethereum_chain = EthereumChain::new(config).await?;
-    let state_contract = StateContract::new(config).await?;
-    let gateway_contract = GatewayContract::new(config).await?;
-    let portal_contract = PortalContract::new(config).await?;
-
+    let watcher = EthereumWatcher {
+        fuel_chain: clients.fuel_chain.clone(),
+        ethereum_chain: clients.ethereum_chain.clone(),
+        state_contract: clients.state_contract.clone(),
+        gateway_contract: clients.gateway_contract.clone(),
+        portal_contract: clients.portal_contract.clone(),
+        alerts: alerts.clone(),
+        actions: actions.clone(),
+        metrics: metrics.clone(),
+        log_tag: config.log_tag(),
+    };
     let watch_config = config.ethereum_client_watcher.clone();
-    let account_address = match &config.ethereum_wallet_key {
-        Some(key) => Some(EthereumChain::get_public_address(key).await?),
+    let account_addresses: Vec<String> = clients.signers.iter().map(EthereumChain::get_public_address).collect();
+    if state.get_last_commit_check_block().is_none() {
+        let last_commit_check_block = watcher
+            .ethereum_chain
+            .get_block_number_from_time_frame(
+                COMMIT_CHECK_STARTING_OFFSET as u32,
+                watcher.ethereum_chain.get_latest_block_number().await?,
+            )
+            .await?;
+        state.set_last_commit_check_block(last_commit_check_block);
+    }
+
+    // checks that look at new blocks run as websocket events arrive, falling back to polling over http
+    let ethereum_events = match EthereumSubscriber::new(config)? {
+        Some(subscriber) => Some(subscriber.start(alerts.clone(), watch_config.connection_alert.alert_level.clone())),
         None => None,
     };
-    let commit_start_block_offset = COMMIT_CHECK_STARTING_OFFSET / ETHEREUM_BLOCK_TIME;
-    let mut last_commit_check_block = max(
-        ethereum_chain.get_latest_block_number().await?,
-        commit_start_block_offset,
-    ) - commit_start_block_offset;
+    let mut scheduler = CheckScheduler::new("Ethereum watcher", status.clone(), alerts, actions);
+
+    // update the log every so often to notify that everything is working
+    let status_watcher = watcher.clone();
+    scheduler.schedule(
+        String::from("ethereum watcher status"),
+        CheckSchedule::new(None, None, POLL_DURATION * POLL_LOGGING_SKIP),
+        AlertLevel::None,
+        EthereumAction::None,
+        move || log_status(status_watcher.clone()),
+    );
+
+    // check chain connection
+    let connection_alert = watch_config.connection_alert.clone();
+    if connection_alert.alert_level != AlertLevel::None {
+        let name = String::from("ethereum connection");
+        let check_watcher = watcher.for_check(&name);
+        let check_alert = connection_alert.clone();
+        scheduler.schedule(
+            name,
+            CheckSchedule::new(
+                connection_alert.check_interval,
+                connection_alert.check_timeout,
+                POLL_DURATION,
+            ),
+            connection_alert.alert_level,
+            connection_alert.alert_action,
+            move || check_connection(check_watcher.clone(), check_alert.clone()),
+        );
+    }
+
+    // check block production
+    let block_production_alert = watch_config.block_production_alert.clone();
+    if block_production_alert.alert_level != AlertLevel::None {
+        let name = String::from("ethereum block production");
+        let check_watcher = watcher.for_check(&name);
+        let check_alert = block_production_alert.clone();
+        let connection_alert = watch_config.connection_alert.clone();
+        scheduler.schedule(
+            name,
+            CheckSchedule::new(
+                block_production_alert.check_interval,
+                block_production_alert.check_timeout,
+                POLL_DURATION,
+            ),
+            block_production_alert.alert_level,
+            block_production_alert.alert_action,
+            move || check_block_production(check_watcher.clone(), check_alert.clone(), connection_alert.clone()),
+        );
+    }
+
+    // check rpc endpoints are consistent with each other
+    let endpoint_consensus_alert = watch_config.endpoint_consensus_alert.clone();
+    if endpoint_consensus_alert.alert_level != AlertLevel::None {
+        let name = String::from("ethereum endpoint consensus");
+        let check_watcher = watcher.for_check(&name);
+        let check_alert = endpoint_consensus_alert.clone();
+        scheduler.schedule(
+            name,
+            CheckSchedule::new(
+                endpoint_consensus_alert.check_interval,
+                endpoint_consensus_alert.check_timeout,
+                POLL_DURATION,
+            ),
+            endpoint_consensus_alert.alert_level,
+            endpoint_consensus_alert.alert_action,
+            move || check_endpoint_consensus(check_watcher.clone(), check_alert.clone()),
+        );
+    }
+
+    // check account balance
+    let account_funds_alert = watch_config.account_funds_alert.clone();
+    if !account_addresses.is_empty() && account_funds_alert.alert_level != AlertLevel::None {
+        let name = String::from("ethereum account funds");
+        let check_watcher = watcher.for_check(&name);
+        let check_alert = account_funds_alert.clone();
+        scheduler.schedule(
+            name,
+            CheckSchedule::new(
+                account_funds_alert.check_interval,
+                account_funds_alert.check_timeout,
+                POLL_DURATION,
+            ),
+            account_funds_alert.alert_level,
+            account_funds_alert.alert_action,
+            move || check_account_funds(check_watcher.clone(), check_alert.clone(), account_addresses.clone()),
+        );
+    }
+
+    // check invalid commits
+    let invalid_state_commit_alert = watch_config.invalid_state_commit_alert.clone();
+    if invalid_state_commit_alert.alert_level != AlertLevel::None {
+        let name = String::from("state contract commits");
+        let check_watcher = watcher.for_check(&name);
+        let check_alert = invalid_state_commit_alert.clone();
+        let commit_check_state = state.clone();
+        scheduler.schedule(
+            name,
+            CheckSchedule::new(
+                invalid_state_commit_alert.check_interval,
+                invalid_state_commit_alert.check_timeout,
+                POLL_DURATION,
+            )
+            .with_trigger(ethereum_events.clone(), WEBSOCKET_POLL_DURATION),
+            invalid_state_commit_alert.alert_level,
+            invalid_state_commit_alert.alert_action,
+            move || check_state_commits(check_watcher.clone(), check_alert.clone(), commit_check_state.clone()),
+        );
+    }
+
+    // check base asset deposits
+    for portal_deposit_alert in watch_config.portal_deposit_alerts {
+        if portal_deposit_alert.alert_level != AlertLevel::None {
+            let name = format!("base asset deposits over {} seconds", portal_deposit_alert.time_frame);
+            let check_watcher = watcher.for_check(&name);
+            let check_alert = portal_deposit_alert.clone();
+            scheduler.schedule(
+                name,
+                CheckSchedule::new(
+                    portal_deposit_alert.check_interval,
+                    portal_deposit_alert.check_timeout,
+                    POLL_DURATION,
+                )
+                .with_trigger(ethereum_events.clone(), WEBSOCKET_POLL_DURATION),
+                portal_deposit_alert.alert_level,
+                portal_deposit_alert.alert_action,
+                move || check_portal_deposits(check_watcher.clone(), check_alert.clone()),
+            );
+        }
+    }
+
+    // check ERC20 token deposits
+    for gateway_deposit_alert in watch_config.gateway_deposit_alerts {
+        if gateway_deposit_alert.alert_level != AlertLevel::None {
+            let name = format!(
+                "{} deposits over {} seconds",
+                gateway_deposit_alert.token_name, gateway_deposit_alert.time_frame
+            );
+            let check_watcher = watcher.for_check(&name);
+            let check_alert = gateway_deposit_alert.clone();
+            scheduler.schedule(
+                name,
+                CheckSchedule::new(
+                    gateway_deposit_alert.check_interval,
+                    gateway_deposit_alert.check_timeout,
+                    POLL_DURATION,
+                )
+                .with_trigger(ethereum_events.clone(), WEBSOCKET_POLL_DURATION),
+                gateway_deposit_alert.alert_level,
+                gateway_deposit_alert.alert_action,
+                move || check_gateway_deposits(check_watcher.clone(), check_alert.clone()),
+            );
+        }
+    }
 
     // start thread
-    let handle = tokio::spawn(async move {
-        loop {
-            // update the log every so often to notify that everything is working
-            alerts.alert(String::from("Watching ethereum chain."), AlertLevel::Info);
-            for _i in 0..POLL_LOGGING_SKIP {
-                // check chain connection
-                if watch_config.connection_alert.alert_level != AlertLevel::None {
-                    match ethereum_chain.check_connection().await {
-                        Ok(_) => {}
-                        Err(e) => {
-                            alerts.alert(
-                                format!("Failed to check ethereum connection: {e}"),
-                                watch_config.connection_alert.alert_level.clone(),
-                            );
-                            actions.action(
-                                watch_config.connection_alert.alert_action.clone(),
-                                Some(watch_config.connection_alert.alert_level.clone()),
-                            );
-                        }
-                    }
-                }
+    let handle = tokio::spawn(scheduler.run());
 
-                // check block production
-                if watch_config.block_production_alert.alert_level != AlertLevel::None {
-                    match ethereum_chain.get_seconds_since_last_block().await {
-                        Ok(seconds_since_last_block) => {
-                            if seconds_since_last_block > watch_config.block_production_alert.max_block_time {
-                                alerts.alert(
-                                    format!(
-                                        "Next ethereum block is taking longer than {} seconds. Last block was {} seconds ago.",
-                                        watch_config.block_production_alert.max_block_time, seconds_since_last_block
-                                    ),
-                                    watch_config.block_production_alert.alert_level.clone(),
-                                );
-                                actions.action(
-                                    watch_config.block_production_alert.alert_action.clone(),
-                                    Some(watch_config.block_production_alert.alert_level.clone()),
-                                );
-                            }
-                        }
-                        Err(e) => {
-                            alerts.alert(
-                                format!("Failed to check ethereum block production: {e}"),
-                                watch_config.connection_alert.alert_level.clone(),
-                            );
-                            actions.action(
-                                watch_config.connection_alert.alert_action.clone(),
-                                Some(watch_config.connection_alert.alert_level.clone()),
-                            );
-                        }
-                    }
-                }
+    Ok(handle)
+}
 
-                // check account balance
-                let account_address = account_address.clone();
-                if account_address.is_some() && watch_config.account_funds_alert.alert_level != AlertLevel::None {
-                    let account_address = account_address.unwrap();
-                    match ethereum_chain.get_account_balance(&account_address).await {
-                        Ok(balance) => {
-                            let min_balance =
-                                EthereumChain::get_value(watch_config.account_funds_alert.min_balance, 18);
-                            if balance < min_balance {
-                                alerts.alert(
-                                    format!(
-                                        "Ethereum account ({}) is low on funds. Current balance: {}",
-                                        &account_address, balance
-                                    ),
-                                    watch_config.account_funds_alert.alert_level.clone(),
-                                );
-                                actions.action(
-                                    watch_config.account_funds_alert.alert_action.clone(),
-                                    Some(watch_config.account_funds_alert.alert_level.clone()),
-                                );
-                            }
-                        }
-                        Err(e) => {
-                            alerts.alert(
-                                format!("Failed to check ethereum account funds: {e}"),
-                                watch_config.account_funds_alert.alert_level.clone(),
-                            );
-                            actions.action(
-                                watch_config.account_funds_alert.alert_action.clone(),
-                                Some(watch_config.account_funds_alert.alert_level.clone()),
-                            );
-                        }
-                    }
+async fn log_status(watcher: EthereumWatcher) -> Result<()> {
+    watcher
+        .alerts
+        .alert(String::from("Watching ethereum chain."), AlertLevel::Info);
+    for metrics in watcher.ethereum_chain.get_rpc_metrics() {
+        log::info!(
+            "{}Ethereum RPC {}: {} requests, {} errors, {} timeouts, {:?} total latency",
+            watcher.log_tag,
+            metrics.url,
+            metrics.requests,
+            metrics.errors,
+            metrics.timeouts,
+            metrics.total_latency
+        );
+    }
+    Ok(())
+}
+
+async fn check_connection(watcher: EthereumWatcher, connection_alert: GenericAlert) -> Result<()> {
+    match watcher.ethereum_chain.check_connection().await {
+        Ok(_) => {}
+        Err(e) => {
+            watcher.alerts.alert(
+                format!("Failed to check ethereum connection: {e}"),
+                connection_alert.alert_level.clone(),
+            );
+            watcher.actions.action(
+                connection_alert.alert_action.clone(),
+                Some(connection_alert.alert_level.clone()),
+            );
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+async fn check_block_production(
+    watcher: EthereumWatcher,
+    block_production_alert: BlockProductionAlert,
+    connection_alert: GenericAlert,
+) -> Result<()> {
+    match watcher.ethereum_chain.get_seconds_since_last_block().await {
+        Ok(seconds_since_last_block) => {
+            watcher
+                .metrics
+                .set_seconds_since_last_block(Chain::Ethereum, seconds_since_last_block);
+            if seconds_since_last_block > block_production_alert.max_block_time {
+                watcher.alerts.alert(
+                    format!(
+                        "Next ethereum block is taking longer than {} seconds. Last block was {} seconds ago.",
+                        block_production_alert.max_block_time, seconds_since_last_block
+                    ),
+                    block_production_alert.alert_level.clone(),
+                );
+                watcher.actions.action(
+                    block_production_alert.alert_action.clone(),
+                    Some(block_production_alert.alert_level.clone()),
+                );
+            }
+        }
+        Err(e) => {
+            watcher.alerts.alert(
+                format!("Failed to check ethereum block production: {e}"),
+                connection_alert.alert_level.clone(),
+            );
+            watcher.actions.action(
+                connection_alert.alert_action.clone(),
+                Some(connection_alert.alert_level.clone()),
+            );
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+async fn check_endpoint_consensus(watcher: EthereumWatcher, endpoint_consensus_alert: GenericAlert) -> Result<()> {
+    match watcher.ethereum_chain.check_endpoint_consensus().await {
+        Ok(_) => {}
+        Err(e) => {
+            watcher.alerts.alert(
+                format!("Ethereum RPC endpoints are inconsistent: {e}"),
+                endpoint_consensus_alert.alert_level.clone(),
+            );
+            watcher.actions.action(
+                endpoint_consensus_alert.alert_action.clone(),
+                Some(endpoint_consensus_alert.alert_level.clone()),
+            );
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+// checks every account actions can be sent from, so a backup account running dry is noticed before it is needed
+async fn check_account_funds(
+    watcher: EthereumWatcher,
+    account_funds_alert: AccountFundsAlert,
+    account_addresses: Vec<String>,
+) -> Result<()> {
+    let min_balance = EthereumChain::get_value(account_funds_alert.min_balance, 18);
+    let mut result = Ok(());
+    for account_address in &account_addresses {
+        match watcher.ethereum_chain.get_account_balance(account_address).await {
+            Ok(balance) => {
+                watcher
+                    .metrics
+                    .set_signer_balance(account_address, EthereumChain::get_units(balance, 18));
+                if balance < min_balance {
+                    watcher.alerts.alert(
+                        format!(
+                            "Ethereum account ({}) is low on funds. Current balance: {}",
+                            account_address, balance
+                        ),
+                        account_funds_alert.alert_level.clone(),
+                    );
+                    watcher.actions.action(
+                        account_funds_alert.alert_action.clone(),
+                        Some(account_funds_alert.alert_level.clone()),
+                    );
                 }
+            }
+            Err(e) => {
+                watcher.alerts.alert(
+                    format!("Failed to check ethereum account ({account_address}) funds: {e}"),
+                    account_funds_alert.alert_level.clone(),
+                );
+                watcher.actions.action(
+                    account_funds_alert.alert_action.clone(),
+                    Some(account_funds_alert.alert_level.clone()),
+                );
+                result = Err(e);
+            }
+        }
+    }
+    result
+}
 
-                // check invalid commits
-                if watch_config.invalid_state_commit_alert.alert_level != AlertLevel::None {
-                    match state_contract.get_latest_commits(last_commit_check_block).await {
-                        Ok(hashes) => {
-                            for hash in hashes {
-                                match fuel_chain.verify_block_commit(&hash).await {
-                                    Ok(valid) => {
-                                        if !valid {
-                                            alerts.alert(
-                                                format!("An invalid commit was made on the state contract. Hash: {hash}"),
-                                                watch_config.invalid_state_commit_alert.alert_level.clone(),
-                                            );
-                                            actions.action(
-                                                watch_config.invalid_state_commit_alert.alert_action.clone(),
-                                                Some(watch_config.invalid_state_commit_alert.alert_level.clone()),
-                                            );
-                                        }
-                                    }
-                                    Err(e) => {
-                                        alerts.alert(
-                                            format!("Failed to check state contract commits: {e}"),
-                                            watch_config.invalid_state_commit_alert.alert_level.clone(),
-                                        );
-                                        actions.action(
-                                            watch_config.invalid_state_commit_alert.alert_action.clone(),
-                                            Some(watch_config.invalid_state_commit_alert.alert_level.clone()),
-                                        );
-                                    }
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            alerts.alert(
-                                format!("Failed to check state contract commits: {e}"),
-                                watch_config.invalid_state_commit_alert.alert_level.clone(),
+async fn check_state_commits(
+    watcher: EthereumWatcher,
+    invalid_state_commit_alert: GenericAlert,
+    state: WatchtowerState,
+) -> Result<()> {
+    // get the latest block to check commits up to
+    let from_block = state.get_last_commit_check_block().unwrap_or(0);
+    let latest_block = match watcher.ethereum_chain.get_latest_block_number().await {
+        Ok(block_num) => block_num,
+        Err(_) => from_block,
+    };
+
+    let result = match watcher
+        .state_contract
+        .get_latest_commits(from_block, latest_block)
+        .await
+    {
+        Ok(hashes) => {
+            let mut result = Ok(());
+            for hash in hashes {
+                match watcher.fuel_chain.verify_block_commit(&hash).await {
+                    Ok(valid) => {
+                        if !valid {
+                            watcher.alerts.alert(
+                                format!("An invalid commit was made on the state contract. Hash: {hash}"),
+                                invalid_state_commit_alert.alert_level.clone(),
                             );
-                            actions.action(
-                                watch_config.invalid_state_commit_alert.alert_action.clone(),
-                                Some(watch_config.invalid_state_commit_alert.alert_level.clone()),
+                            watcher.actions.action(
+                                invalid_state_commit_alert.alert_action.clone(),
+                                Some(invalid_state_commit_alert.alert_level.clone()),
                             );
                         }
                     }
-                    last_commit_check_block = match ethereum_chain.get_latest_block_number().await {
-                        Ok(block_num) => block_num,
-                        Err(_) => last_commit_check_block,
-                    };
-                }
-
-                // check base asset deposits
-                for portal_deposit_alert in &watch_config.portal_deposit_alerts {
-                    if portal_deposit_alert.alert_level != AlertLevel::None {
-                        let latest_block = last_commit_check_block;
-                        let time_frame = portal_deposit_alert.time_frame;
-                        match portal_contract.get_amount_deposited(time_frame, latest_block).await {
-                            Ok(amount) => {
-                                println!("Total ETH deposited: {:?}", amount);
-                                let amount_threshold = EthereumChain::get_value(portal_deposit_alert.amount, 18);
-                                if amount >= amount_threshold {
-                                    alerts.alert(
-                                        format!(
-                                            "Base asset deposit threshold of {} over {} seconds has been reached. Amount deposited: {}",
-                                            amount_threshold, time_frame, amount
-                                        ),
-                                        portal_deposit_alert.alert_level.clone(),
-                                    );
-                                    actions.action(
-                                        portal_deposit_alert.alert_action.clone(),
-                                        Some(portal_deposit_alert.alert_level.clone()),
-                                    );
-                                }
-                            }
-                            Err(e) => {
-                                alerts.alert(
-                                    format!("Failed to check base asset deposits: {e}"),
-                                    portal_deposit_alert.alert_level.clone(),
-                                );
-                                actions.action(
-                                    portal_deposit_alert.alert_action.clone(),
-                                    Some(portal_deposit_alert.alert_level.clone()),
-                                );
-                            }
-                        }
+                    Err(e) => {
+                        watcher.alerts.alert(
+                            format!("Failed to check state contract commits: {e}"),
+                            invalid_state_commit_alert.alert_level.clone(),
+                        );
+                        watcher.actions.action(
+                            invalid_state_commit_alert.alert_action.clone(),
+                            Some(invalid_state_commit_alert.alert_level.clone()),
+                        );
+                        result = Err(e);
                     }
                 }
+            }
+            result
+        }
+        Err(e) => {
+            watcher.alerts.alert(
+                format!("Failed to check state contract commits: {e}"),
+                invalid_state_commit_alert.alert_level.clone(),
+            );
+            watcher.actions.action(
+                invalid_state_commit_alert.alert_action.clone(),
+                Some(invalid_state_commit_alert.alert_level.clone()),
+            );
+            Err(e)
+        }
+    };
+    if result.is_ok() {
+        watcher.metrics.set_last_verified_commit_block(latest_block);
+    }
+    state.set_last_commit_check_block(latest_block);
+    result
+}
 
-                // check ERC20 token deposits
-                for gateway_deposit_alert in &watch_config.gateway_deposit_alerts {
-                    if gateway_deposit_alert.alert_level != AlertLevel::None {
-                        let latest_block = last_commit_check_block;
-                        match gateway_contract
-                            .get_amount_deposited(
-                                gateway_deposit_alert.time_frame,
-                                &gateway_deposit_alert.token_address,
-                                latest_block,
-                            )
-                            .await
-                        {
-                            Ok(amount) => {
-                                println!("Total Tokens deposited: {:?}", amount);
-                                let amount_threshold = EthereumChain::get_value(
-                                    gateway_deposit_alert.amount,
-                                    gateway_deposit_alert.token_decimals,
-                                );
-                                if amount >= amount_threshold {
-                                    alerts.alert(
-                                        format!(
-                                            "ERC20 deposit threshold of {}{} over {} seconds has been reached. Amount deposited: {}{}",
-                                            amount_threshold, gateway_deposit_alert.token_name, gateway_deposit_alert.time_frame, amount, gateway_deposit_alert.token_name
-                                        ),
-                                        gateway_deposit_alert.alert_level.clone(),
-                                    );
-                                    actions.action(
-                                        gateway_deposit_alert.alert_action.clone(),
-                                        Some(gateway_deposit_alert.alert_level.clone()),
-                                    );
-                                }
-                            }
-                            Err(e) => {
-                                alerts.alert(
-                                    format!("Failed to check ERC20 deposits: {e}"),
-                                    gateway_deposit_alert.alert_level.clone(),
-                                );
-                                actions.action(
-                                    gateway_deposit_alert.alert_action.clone(),
-                                    Some(gateway_deposit_alert.alert_level.clone()),
-                                );
-                            }
-                        }
-                    }
+async fn check_portal_deposits(watcher: EthereumWatcher, portal_deposit_alert: DepositAlert) -> Result<()> {
+    let time_frame = portal_deposit_alert.time_frame;
+    let amount_result = match watcher.ethereum_chain.get_latest_block_number().await {
+        Ok(latest_block) => {
+            match watcher
+                .ethereum_chain
+                .get_block_number_from_time_frame(time_frame, latest_block)
+                .await
+            {
+                Ok(start_block) => {
+                    watcher
+                        .portal_contract
+                        .get_amount_deposited(start_block, latest_block)
+                        .await
                 }
-
-                thread::sleep(POLL_DURATION);
+                Err(e) => Err(e),
+            }
+        }
+        Err(e) => Err(e),
+    };
+    match amount_result {
+        Ok(amount) => {
+            watcher.metrics.set_window_total(
+                Direction::Deposit,
+                "ETH",
+                time_frame,
+                EthereumChain::get_units(amount, 18),
+            );
+            let amount_threshold = EthereumChain::get_value(portal_deposit_alert.amount, 18);
+            if amount >= amount_threshold {
+                watcher.alerts.alert(
+                    format!(
+                        "Base asset deposit threshold of {} over {} seconds has been reached. Amount deposited: {}",
+                        amount_threshold, time_frame, amount
+                    ),
+                    portal_deposit_alert.alert_level.clone(),
+                );
+                watcher.actions.action(
+                    portal_deposit_alert.alert_action.clone(),
+                    Some(portal_deposit_alert.alert_level.clone()),
+                );
             }
         }
-    });
+        Err(e) => {
+            watcher.alerts.alert(
+                format!("Failed to check base asset deposits: {e}"),
+                portal_deposit_alert.alert_level.clone(),
+            );
+            watcher.actions.action(
+                portal_deposit_alert.alert_action.clone(),
+                Some(portal_deposit_alert.alert_level.clone()),
+            );
+            return Err(e);
+        }
+    }
+    Ok(())
+}
 
-    Ok(handle)
+async fn check_gateway_deposits(watcher: EthereumWatcher, gateway_deposit_alert: DepositAlert) -> Result<()> {
+    let amount_result = match watcher.ethereum_chain.get_latest_block_number().await {
+        Ok(latest_block) => {
+            match watcher
+                .ethereum_chain
+                .get_block_number_from_time_frame(gateway_deposit_alert.time_frame, latest_block)
+                .await
+            {
+                Ok(start_block) => {
+                    watcher
+                        .gateway_contract
+                        .get_amount_deposited(start_block, latest_block, &gateway_deposit_alert.token_address)
+                        .await
+                }
+                Err(e) => Err(e),
+            }
+        }
+        Err(e) => Err(e),
+    };
+    match amount_result {
+        Ok(amount) => {
+            watcher.metrics.set_window_total(
+                Direction::Deposit,
+                &gateway_deposit_alert.token_name,
+                gateway_deposit_alert.time_frame,
+                EthereumChain::get_units(amount, gateway_deposit_alert.token_decimals),
+            );
+            let amount_threshold =
+                EthereumChain::get_value(gateway_deposit_alert.amount, gateway_deposit_alert.token_decimals);
+            if amount >= amount_threshold {
+                watcher.alerts.alert(
+                    format!(
+                        "ERC20 deposit threshold of {}{} over {} seconds has been reached. Amount deposited: {}{}",
+                        amount_threshold,
+                        gateway_deposit_alert.token_name,
+                        gateway_deposit_alert.time_frame,
+                        amount,
+                        gateway_deposit_alert.token_name
+                    ),
+                    gateway_deposit_alert.alert_level.clone(),
+                );
+                watcher.actions.action(
+                    gateway_deposit_alert.alert_action.clone(),
+                    Some(gateway_deposit_alert.alert_level.clone()),
+                );
+            }
+        }
+        Err(e) => {
+            watcher.alerts.alert(
+                format!("Failed to check ERC20 deposits: {e}"),
+                gateway_deposit_alert.alert_level.clone(),
+            );
+            watcher.actions.action(
+                gateway_deposit_alert.alert_action.clone(),
+                Some(gateway_deposit_alert.alert_level.clone()),
+            );
+            return Err(e);
+        }
+    }
+    Ok(())
 }
@@ -0,0 +1,255 @@
+use super::ethereum_client::EthereumClient;
+use super::ethereum_signer::{check_account, WatchtowerSigner};
+use super::preflight::preflight;
+use super::private_relay::PrivateRelay;
+use super::TX_CONFIRMATION_TIMEOUT;
+use crate::WatchtowerConfig;
+
+use anyhow::Result;
+use ethers::abi::Abi;
+use ethers::contract::builders::ContractCall;
+use ethers::contract::Contract;
+use ethers::prelude::SignerMiddleware;
+use ethers::providers::{Middleware, PendingTransaction, Provider};
+use ethers::signers::Signer;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Bytes, TransactionReceipt, H160, H256, U256, U64};
+
+// pauses one of the bridge contracts from the accounts set up to pause it. The contracts all share the same pauser
+// role and pause interface, so only their pause() and paused() calls are needed to relay, simulate, send and confirm
+// the pause
+#[derive(Clone, Debug)]
+pub struct ContractPauser {
+    name: &'static str,
+    provider: Provider<EthereumClient>,
+    contract: Contract<Provider<EthereumClient>>,
+    pause: ContractCall<Provider<EthereumClient>, ()>,
+    paused: ContractCall<Provider<EthereumClient>, bool>,
+    pausers: Vec<SignerMiddleware<Provider<EthereumClient>, WatchtowerSigner>>,
+    private_relay: Option<PrivateRelay>,
+}
+
+impl ContractPauser {
+    pub async fn new(
+        config: &WatchtowerConfig,
+        name: &'static str,
+        contract: &Contract<Provider<EthereumClient>>,
+        pause: ContractCall<Provider<EthereumClient>, ()>,
+        paused: ContractCall<Provider<EthereumClient>, bool>,
+        signers: &[WatchtowerSigner],
+    ) -> Result<Self> {
+        // a separate client able to pause the contract for each configured account
+        let provider = contract.client().as_ref().clone();
+        let mut pausers = vec![];
+        if !signers.is_empty() {
+            let chain_id = provider.get_chainid().await?.as_u64();
+            for signer in signers {
+                pausers.push(SignerMiddleware::new(
+                    provider.clone(),
+                    signer.clone().with_chain_id(chain_id),
+                ));
+            }
+        }
+
+        Ok(ContractPauser {
+            name,
+            provider,
+            contract: contract.clone(),
+            pause,
+            paused,
+            pausers,
+            private_relay: PrivateRelay::new(config)?,
+        })
+    }
+
+    pub async fn is_paused(&self) -> Result<bool> {
+        match self.paused.call().await {
+            Err(e) => Err(anyhow::anyhow!(
+                "Failed to check if {} contract is paused: {}",
+                self.name,
+                e
+            )),
+            Ok(paused) => Ok(paused),
+        }
+    }
+
+    // to decode the reverts of calls to the contract made some other way (such as through a Safe)
+    pub fn get_abi(&self) -> &Abi {
+        self.contract.abi()
+    }
+
+    // the call that pauses the contract, for accounts that send it some other way (such as through a Safe)
+    pub fn get_pause_call(&self) -> Result<(H160, Bytes)> {
+        match self.pause.calldata() {
+            Some(data) => Ok((self.contract.address(), data)),
+            None => Err(anyhow::anyhow!("Failed to encode {} contract pause.", self.name)),
+        }
+    }
+
+    // number of accounts able to pause the contract, in order of preference
+    pub fn pauser_count(&self) -> usize {
+        self.pausers.len()
+    }
+
+    pub fn get_pauser_address(&self, pauser: usize) -> Result<H160> {
+        Ok(self.get_pauser(pauser)?.address())
+    }
+
+    // checks the account can be relied on to land a pause: it holds the pauser role, has at least the min balance
+    // and no transaction of its own stuck pending
+    pub async fn check_pauser(&self, pauser: usize, min_balance: U256) -> Result<()> {
+        let address = self.get_pauser_address(pauser)?;
+        let role = self.contract.method::<_, [u8; 32]>("PAUSER_ROLE", ())?.call().await?;
+        if !self
+            .contract
+            .method::<_, bool>("hasRole", (role, address))?
+            .call()
+            .await?
+        {
+            return Err(anyhow::anyhow!(
+                "{:?} does not have the pauser role on the {} contract.",
+                address,
+                self.name
+            ));
+        }
+        check_account(&self.provider, address, min_balance).await
+    }
+
+    // estimates the gas of the pause from the account without sending it, to check it would go through (and why not)
+    pub async fn simulate_pause(&self, pauser: usize) -> Result<()> {
+        let tx = self.get_pause_tx(pauser)?;
+        match preflight(&self.provider, self.contract.abi(), &tx).await {
+            Err(e) => Err(anyhow::anyhow!("Pausing {} contract would fail: {}", self.name, e)),
+            Ok(_) => Ok(()),
+        }
+    }
+
+    pub async fn pause(&self, pauser: usize) -> Result<()> {
+        let tx_hash = self.send_pause(pauser).await?;
+        self.confirm_pause(tx_hash).await?;
+        Ok(())
+    }
+
+    // sends the pause and returns the hash of its transaction, without waiting for it to be mined
+    pub async fn send_pause(&self, pauser: usize) -> Result<H256> {
+        // never send a pause that is bound to revert
+        self.simulate_pause(pauser).await?;
+        let tx = self.get_pause_tx(pauser)?;
+        let pauser = self.get_pauser(pauser)?;
+
+        // TODO: implement a gas escalator (https://github.com/gakonst/ethers-rs/blob/master/examples/middleware/examples/gas_escalator.rs)
+        match &self.private_relay {
+            // kept out of the public mempool for as long as the relay allows
+            Some(relay) => match relay.send(pauser, tx).await {
+                Err(e) => Err(anyhow::anyhow!("Failed to pause {} contract: {}", self.name, e)),
+                Ok(tx_hash) => Ok(tx_hash),
+            },
+            None => match pauser.send_transaction(tx, None).await {
+                Err(e) => Err(anyhow::anyhow!("Failed to pause {} contract: {}", self.name, e)),
+                Ok(pending_tx) => Ok(pending_tx.tx_hash()),
+            },
+        }
+    }
+
+    // waits for the pause transaction to be confirmed
+    pub async fn confirm_pause(&self, tx_hash: H256) -> Result<TransactionReceipt> {
+        let pending_tx = PendingTransaction::new(tx_hash, &self.provider);
+        match tokio::time::timeout(TX_CONFIRMATION_TIMEOUT, pending_tx).await {
+            Err(_) => Err(anyhow::anyhow!(
+                "Timed out waiting for {} contract pause transaction {:?} to confirm.",
+                self.name,
+                tx_hash
+            )),
+            Ok(Err(e)) => Err(anyhow::anyhow!("Failed to pause {} contract: {}", self.name, e)),
+            Ok(Ok(None)) => Err(anyhow::anyhow!(
+                "The {} contract pause transaction {:?} was dropped.",
+                self.name,
+                tx_hash
+            )),
+            Ok(Ok(Some(receipt))) => {
+                if receipt.status == Some(U64::from(1)) {
+                    Ok(receipt)
+                } else {
+                    Err(anyhow::anyhow!(
+                        "The {} contract pause transaction {:?} reverted.",
+                        self.name,
+                        tx_hash
+                    ))
+                }
+            }
+        }
+    }
+
+    // the pause call as sent from the account
+    fn get_pause_tx(&self, pauser: usize) -> Result<TypedTransaction> {
+        let mut tx = self.pause.tx.clone();
+        tx.set_from(self.get_pauser(pauser)?.address());
+        Ok(tx)
+    }
+
+    fn get_pauser(&self, pauser: usize) -> Result<&SignerMiddleware<Provider<EthereumClient>, WatchtowerSigner>> {
+        match self.pausers.get(pauser) {
+            Some(pauser) => Ok(pauser),
+            None if self.pausers.is_empty() => Err(anyhow::anyhow!(
+                "Ethereum account not configured, the {} contract is read only.",
+                self.name
+            )),
+            None => Err(anyhow::anyhow!(
+                "No pause account {} for the {} contract.",
+                pauser,
+                self.name
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ethereum_watcher::ethereum_client::EthereumClient;
+    use crate::ethereum_watcher::ethereum_signer::WatchtowerSigner;
+    use crate::ethereum_watcher::state_contract::StateContract;
+    use crate::test_utils::{test_config, MockServer};
+    use ethers::providers::Provider;
+    use ethers::signers::Signer;
+    use ethers::types::{Address, Bytes, U256, U64};
+    use serde_json::json;
+    use std::str::FromStr;
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn pausers_without_the_role_are_rejected() {
+        // every call answers zero, so the pauser role is never granted
+        let endpoint = MockServer::json_rpc(|method, _| match method {
+            "eth_chainId" => Ok(json!(U64::from(5))),
+            "eth_blockNumber" => Ok(json!(U64::from(100))),
+            "eth_call" => Ok(json!(Bytes::from(vec![0u8; 32]))),
+            _ => Err(json!({ "code": -32601, "message": "method not found" })),
+        });
+        let mut config = test_config();
+        config.ethereum_rpc = vec![endpoint.url.clone()];
+        config.ethereum_rpc_quorum = 1;
+        let signer =
+            WatchtowerSigner::from_key("4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318").unwrap();
+        let provider = Provider::new(EthereumClient::new(&config).unwrap());
+        let state_contract = StateContract::new(&config, provider, std::slice::from_ref(&signer))
+            .await
+            .unwrap();
+        let pauser = state_contract.get_pauser();
+
+        assert_eq!(pauser.pauser_count(), 1);
+        assert_eq!(pauser.get_pauser_address(0).unwrap(), signer.address());
+        assert_eq!(
+            pauser.get_pauser_address(1).unwrap_err().to_string(),
+            "No pause account 1 for the state contract."
+        );
+        let (to, data) = pauser.get_pause_call().unwrap();
+        assert_eq!(to, Address::from_str(&config.state_contract_address).unwrap());
+        assert_eq!(data, Bytes::from(vec![0x84, 0x56, 0xcb, 0x59]));
+        assert_eq!(
+            pauser.check_pauser(0, U256::zero()).await.unwrap_err().to_string(),
+            format!(
+                "{:?} does not have the pauser role on the state contract.",
+                signer.address()
+            )
+        );
+    }
+}
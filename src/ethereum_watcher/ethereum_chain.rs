@@ -1,12 +1,15 @@
+use super::ethereum_client::{EthereumClient, EthereumEndpointMetrics};
+use super::ethereum_signer::WatchtowerSigner;
 use super::ETHEREUM_CONNECTION_RETRIES;
+use crate::block_timestamps::BlockTimestamps;
 use crate::WatchtowerConfig;
 
 use anyhow::Result;
-use ethers::prelude::k256::ecdsa::SigningKey;
-use ethers::providers::{Http, Middleware, Provider};
-use ethers::signers::{Signer, Wallet};
+use ethers::providers::{Middleware, Provider};
+use ethers::signers::Signer;
 use ethers::types::Address;
 use ethers::utils::hex::ToHex;
+use std::cmp::max;
 use std::ops::Mul;
 use std::str::FromStr;
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -15,20 +18,31 @@ pub use ethers::types::U256;
 
 #[derive(Clone, Debug)]
 pub struct EthereumChain {
-    provider: Provider<Http>,
+    provider: Provider<EthereumClient>,
+    chain_id: u64,
+    rpc_max_lag: u64,
+    block_timestamps: BlockTimestamps,
 }
 
 impl EthereumChain {
-    pub async fn new(config: &WatchtowerConfig) -> Result<Self> {
-        // setup provider and check that it is valid
-        let provider = Provider::<Http>::try_from(&config.ethereum_rpc)?;
+    pub async fn new(config: &WatchtowerConfig, provider: Provider<EthereumClient>) -> Result<Self> {
+        // check that the provider is valid
         let provider_result = provider.get_chainid().await;
         match provider_result {
             Err(e) => Err(anyhow::anyhow!("Invalid ethereum RPC: {e}")),
-            Ok(_) => Ok(EthereumChain { provider }),
+            Ok(chain_id) => Ok(EthereumChain {
+                provider,
+                chain_id: chain_id.as_u64(),
+                rpc_max_lag: config.ethereum_rpc_max_lag,
+                block_timestamps: BlockTimestamps::new(),
+            }),
         }
     }
 
+    pub fn get_chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
     pub async fn check_connection(&self) -> Result<()> {
         for i in 0..ETHEREUM_CONNECTION_RETRIES {
             match self.provider.get_chainid().await {
@@ -43,6 +57,14 @@ impl EthereumChain {
         Ok(())
     }
 
+    pub async fn check_endpoint_consensus(&self) -> Result<()> {
+        self.provider.as_ref().check_endpoints(self.rpc_max_lag).await
+    }
+
+    pub fn get_rpc_metrics(&self) -> Vec<EthereumEndpointMetrics> {
+        self.provider.as_ref().metrics()
+    }
+
     pub async fn get_seconds_since_last_block(&self) -> Result<u32> {
         let block_num = self.get_latest_block_number().await?;
         for i in 0..ETHEREUM_CONNECTION_RETRIES {
@@ -86,6 +108,43 @@ impl EthereumChain {
         Ok(0)
     }
 
+    pub async fn get_block_timestamp(&self, block_num: u64) -> Result<u64> {
+        if let Some(timestamp) = self.block_timestamps.get(block_num) {
+            return Ok(timestamp);
+        }
+        for i in 0..ETHEREUM_CONNECTION_RETRIES {
+            match self.provider.get_block(block_num).await {
+                Ok(block_result) => {
+                    return match block_result {
+                        Some(block) => {
+                            let timestamp = block.timestamp.as_u64();
+                            self.block_timestamps.insert(block_num, timestamp);
+                            Ok(timestamp)
+                        }
+                        None => Err(anyhow::anyhow!("Failed to get block {block_num}")),
+                    }
+                }
+                Err(e) => {
+                    if i == ETHEREUM_CONNECTION_RETRIES - 1 {
+                        return Err(anyhow::anyhow!("{e}"));
+                    }
+                }
+            }
+        }
+        Ok(0)
+    }
+
+    // finds the first block produced within the last `time_frame` seconds
+    pub async fn get_block_number_from_time_frame(&self, time_frame: u32, latest_block_num: u64) -> Result<u64> {
+        // block timestamps are strictly increasing so there can be at most one block per second
+        let low = max(latest_block_num, time_frame as u64) - time_frame as u64;
+        self.block_timestamps
+            .find_block_from_time_frame(time_frame, low, latest_block_num, |block_num| {
+                self.get_block_timestamp(block_num)
+            })
+            .await
+    }
+
     pub async fn get_account_balance(&self, addr: &str) -> Result<U256> {
         for i in 0..ETHEREUM_CONNECTION_RETRIES {
             match self.provider.get_balance(Address::from_str(addr)?, None).await {
@@ -100,18 +159,21 @@ impl EthereumChain {
         Ok(U256::zero())
     }
 
-    pub async fn get_public_address(key_str: &str) -> Result<String> {
-        let wallet: Wallet<SigningKey> = key_str.parse::<Wallet<SigningKey>>()?;
-        Ok(wallet.address().encode_hex())
+    pub fn get_public_address(signer: &WatchtowerSigner) -> String {
+        signer.address().encode_hex()
     }
 
     pub fn get_value(value_fp: f64, decimals: u8) -> U256 {
         let decimals_p1 = if decimals < 9 { decimals } else { decimals - 9 };
         let decimals_p2 = decimals - decimals_p1;
 
-        let value = value_fp * (10.0 as f64).powf(decimals_p1 as f64);
-        let value = U256::from(value as u64);
-        let value = value.mul((10 as u64).pow(decimals_p2 as u32));
-        value
+        let value = value_fp * 10f64.powf(decimals_p1 as f64);
+        U256::from(value as u64).mul(10u64.pow(decimals_p2 as u32))
+    }
+
+    // inverse of get_value, for reporting amounts in whole tokens
+    pub fn get_units(value: U256, decimals: u8) -> f64 {
+        let value = value.to_string().parse::<f64>().unwrap_or(f64::NAN);
+        value / 10f64.powi(decimals as i32)
     }
 }
@@ -0,0 +1,359 @@
+use super::ethereum_chain::EthereumChain;
+use super::ethereum_client::EthereumClient;
+use crate::config::{KeystoreConfig, RemoteSignerConfig, KEYSTORE_PASSWORD_ENV_VAR};
+use crate::WatchtowerConfig;
+
+use async_trait::async_trait;
+use ethers::prelude::k256::ecdsa::SigningKey;
+use ethers::providers::{Http, JsonRpcClient, Middleware, Provider};
+use ethers::signers::{Signer, Wallet, WalletError};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::transaction::eip712::Eip712;
+use ethers::types::{Address, BlockNumber, Bytes, Signature, U256, U64};
+use ethers::utils::rlp::Rlp;
+use reqwest::{Client, Url};
+use serde_json::{json, Value};
+use std::env;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::str::FromStr;
+use std::time::Duration;
+
+// the account actions are sent from. The key is either held in memory (from the config or an encrypted keystore) or
+// kept by a remote signer, in which case it never reaches the watchtower
+#[derive(Clone, Debug)]
+pub enum WatchtowerSigner {
+    Local(Wallet<SigningKey>),
+    Remote(RemoteSigner),
+}
+
+// signs through a web3signer compatible json-rpc endpoint (eth_signTransaction and eth_sign). A local dev node
+// managing unlocked accounts (such as anvil) can stand in for it in tests
+#[derive(Clone, Debug)]
+pub struct RemoteSigner {
+    url: String,
+    transport: Http,
+    address: Address,
+    chain_id: u64,
+    timeout: Duration,
+}
+
+#[derive(Debug)]
+pub enum WatchtowerSignerError {
+    Wallet(WalletError),
+    Remote(String),
+}
+
+impl Display for WatchtowerSignerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WatchtowerSignerError::Wallet(e) => write!(f, "{e}"),
+            WatchtowerSignerError::Remote(e) => write!(f, "Remote signer error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for WatchtowerSignerError {}
+
+impl From<WalletError> for WatchtowerSignerError {
+    fn from(e: WalletError) -> Self {
+        WatchtowerSignerError::Wallet(e)
+    }
+}
+
+impl WatchtowerSigner {
+    // sets up every account configured for the network, in the order they are listed
+    pub async fn from_config(config: &WatchtowerConfig) -> anyhow::Result<Vec<Self>> {
+        let mut signers = vec![];
+        for (i, key) in config.ethereum_wallet_key.iter().enumerate() {
+            let signer = Self::from_key(key).map_err(|e| anyhow::anyhow!("ethereum_wallet_key[{}]: {}", i, e))?;
+            signers.push(signer);
+        }
+        for keystore in &config.ethereum_keystore {
+            signers.push(Self::from_keystore(keystore)?);
+        }
+        for remote_signer in &config.ethereum_remote_signer {
+            signers.push(Self::remote(remote_signer).await?);
+        }
+        Ok(signers)
+    }
+
+    pub fn from_key(key: &str) -> anyhow::Result<Self> {
+        Ok(WatchtowerSigner::Local(key.parse::<Wallet<SigningKey>>()?))
+    }
+
+    // decrypts the keystore with the password read from the password file, or from the environment if no file is set
+    pub fn from_keystore(config: &KeystoreConfig) -> anyhow::Result<Self> {
+        let password = match &config.password_file {
+            Some(password_file) => fs::read_to_string(password_file)
+                .map_err(|e| anyhow::anyhow!("Failed to read keystore password file {}: {}", password_file, e))?
+                .trim_end_matches(&['\r', '\n'][..])
+                .to_string(),
+            None => env::var(KEYSTORE_PASSWORD_ENV_VAR).map_err(|_| {
+                anyhow::anyhow!(
+                    "No keystore password given. Set ethereum_keystore.password_file or the {} environment variable.",
+                    KEYSTORE_PASSWORD_ENV_VAR
+                )
+            })?,
+        };
+        match Wallet::<SigningKey>::decrypt_keystore(&config.path, password) {
+            Ok(wallet) => Ok(WatchtowerSigner::Local(wallet)),
+            Err(e) => Err(anyhow::anyhow!("Failed to decrypt keystore {}: {}", config.path, e)),
+        }
+    }
+
+    // connects to the remote signer and checks it holds the key of the configured address. Transactions are signed
+    // for the chain the signer reports unless they name their own
+    pub async fn remote(config: &RemoteSignerConfig) -> anyhow::Result<Self> {
+        let mut signer = RemoteSigner {
+            url: config.url.clone(),
+            transport: Http::new_with_client(Url::parse(&config.url)?, Client::new()),
+            address: Address::from_str(&config.address)?,
+            chain_id: 0,
+            timeout: Duration::from_secs(config.timeout),
+        };
+        let chain_id: U64 = serde_json::from_value(signer.request("eth_chainId", json!([])).await?)?;
+        signer.chain_id = chain_id.as_u64();
+        let accounts: Vec<Address> = serde_json::from_value(signer.request("eth_accounts", json!([])).await?)?;
+        if !accounts.contains(&signer.address) {
+            return Err(anyhow::anyhow!(
+                "Remote signer {} does not hold the key of {:?}.",
+                signer.url,
+                signer.address
+            ));
+        }
+        Ok(WatchtowerSigner::Remote(signer))
+    }
+}
+
+// checks the account can be relied on to get a transaction mined: it has at least the min balance, and no transaction
+// of its own still pending that a new one would be stuck behind
+pub async fn check_account(
+    provider: &Provider<EthereumClient>,
+    address: Address,
+    min_balance: U256,
+) -> anyhow::Result<()> {
+    let balance = provider.get_balance(address, None).await?;
+    if balance < min_balance {
+        return Err(anyhow::anyhow!(
+            "{:?} is low on funds. Current balance: {}",
+            address,
+            EthereumChain::get_units(balance, 18)
+        ));
+    }
+    let (mined, pending) = tokio::try_join!(
+        provider.get_transaction_count(address, Some(BlockNumber::Latest.into())),
+        provider.get_transaction_count(address, Some(BlockNumber::Pending.into())),
+    )?;
+    if pending > mined {
+        return Err(anyhow::anyhow!(
+            "{:?} has {} transactions stuck pending from nonce {}.",
+            address,
+            pending - mined,
+            mined
+        ));
+    }
+    Ok(())
+}
+
+impl RemoteSigner {
+    async fn request(&self, method: &str, params: Value) -> Result<Value, WatchtowerSignerError> {
+        match tokio::time::timeout(self.timeout, self.transport.request(method, params)).await {
+            Ok(Ok(value)) => Ok(value),
+            Ok(Err(e)) => Err(WatchtowerSignerError::Remote(format!(
+                "{} failed on {}: {}",
+                method, self.url, e
+            ))),
+            Err(_) => Err(WatchtowerSignerError::Remote(format!(
+                "{} timed out on {} after {} seconds",
+                method,
+                self.url,
+                self.timeout.as_secs()
+            ))),
+        }
+    }
+
+    // the signer returns the signed transaction, whose signature must be for exactly the transaction that was asked
+    // to be signed and by the configured address
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, WatchtowerSignerError> {
+        let mut tx = tx.clone();
+        tx.set_from(self.address);
+        if tx.chain_id().is_none() {
+            tx.set_chain_id(self.chain_id);
+        }
+        let signed_tx: Bytes = serde_json::from_value(self.request("eth_signTransaction", json!([tx])).await?)
+            .map_err(|e| WatchtowerSignerError::Remote(format!("Invalid signed transaction: {e}")))?;
+        let (_, signature) = TypedTransaction::decode_signed(&Rlp::new(&signed_tx))
+            .map_err(|e| WatchtowerSignerError::Remote(format!("Invalid signed transaction: {e}")))?;
+        signature
+            .verify(tx.sighash(), self.address)
+            .map_err(|e| WatchtowerSignerError::Remote(format!("Invalid transaction signature: {e}")))?;
+        Ok(signature)
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Signature, WatchtowerSignerError> {
+        let params = json!([self.address, Bytes::from(message.to_vec())]);
+        let signature: String = serde_json::from_value(self.request("eth_sign", params).await?)
+            .map_err(|e| WatchtowerSignerError::Remote(format!("Invalid signature: {e}")))?;
+        let signature = Signature::from_str(&signature)
+            .map_err(|e| WatchtowerSignerError::Remote(format!("Invalid signature: {e}")))?;
+        signature
+            .verify(message, self.address)
+            .map_err(|e| WatchtowerSignerError::Remote(format!("Invalid signature: {e}")))?;
+        Ok(signature)
+    }
+}
+
+#[async_trait]
+impl Signer for WatchtowerSigner {
+    type Error = WatchtowerSignerError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(&self, message: S) -> Result<Signature, Self::Error> {
+        match self {
+            WatchtowerSigner::Local(wallet) => Ok(wallet.sign_message(message).await?),
+            WatchtowerSigner::Remote(signer) => signer.sign_message(message.as_ref()).await,
+        }
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, Self::Error> {
+        match self {
+            WatchtowerSigner::Local(wallet) => Ok(wallet.sign_transaction(tx).await?),
+            WatchtowerSigner::Remote(signer) => signer.sign_transaction(tx).await,
+        }
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(&self, payload: &T) -> Result<Signature, Self::Error> {
+        match self {
+            WatchtowerSigner::Local(wallet) => Ok(wallet.sign_typed_data(payload).await?),
+            WatchtowerSigner::Remote(_) => Err(WatchtowerSignerError::Remote(String::from(
+                "Signing typed data is not supported.",
+            ))),
+        }
+    }
+
+    fn address(&self) -> Address {
+        match self {
+            WatchtowerSigner::Local(wallet) => wallet.address(),
+            WatchtowerSigner::Remote(signer) => signer.address,
+        }
+    }
+
+    fn chain_id(&self) -> u64 {
+        match self {
+            WatchtowerSigner::Local(wallet) => wallet.chain_id(),
+            WatchtowerSigner::Remote(signer) => signer.chain_id,
+        }
+    }
+
+    fn with_chain_id<T: Into<u64>>(self, chain_id: T) -> Self {
+        match self {
+            WatchtowerSigner::Local(wallet) => WatchtowerSigner::Local(wallet.with_chain_id(chain_id)),
+            WatchtowerSigner::Remote(signer) => WatchtowerSigner::Remote(RemoteSigner {
+                chain_id: chain_id.into(),
+                ..signer
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::MockServer;
+    use ethers::types::TransactionRequest;
+    use ethers::utils::hash_message;
+
+    static KEY: &str = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+    static OTHER_KEY: &str = "646f1ce2fdad0e6deeeb5c7e8e5543bdde65e86029e2fd9fc169899c440a7913";
+
+    // a remote signer on chain 5 that signs with `key`, after letting `tamper` change the transaction it was asked to
+    // sign
+    fn remote_signer(key: &'static str, tamper: fn(&mut TypedTransaction)) -> MockServer {
+        let wallet = key.parse::<Wallet<SigningKey>>().unwrap().with_chain_id(5u64);
+        MockServer::json_rpc(move |method, params| match method {
+            "eth_chainId" => Ok(json!(U64::from(5))),
+            "eth_accounts" => Ok(json!([KEY.parse::<Wallet<SigningKey>>().unwrap().address()])),
+            "eth_signTransaction" => {
+                let mut tx: TypedTransaction = serde_json::from_value(params[0].clone()).unwrap();
+                tamper(&mut tx);
+                let signature = wallet.sign_transaction_sync(&tx);
+                Ok(json!(tx.rlp_signed(&signature)))
+            }
+            "eth_sign" => {
+                let message: Bytes = serde_json::from_value(params[1].clone()).unwrap();
+                let signature = wallet.sign_hash(hash_message(message));
+                Ok(json!(format!("0x{signature}")))
+            }
+            _ => Err(json!({ "code": -32601, "message": "method not found" })),
+        })
+    }
+
+    async fn connect(server: &MockServer) -> anyhow::Result<WatchtowerSigner> {
+        let address = KEY.parse::<Wallet<SigningKey>>().unwrap().address();
+        WatchtowerSigner::remote(&RemoteSignerConfig {
+            url: server.url.clone(),
+            address: format!("{address:?}"),
+            timeout: 10,
+        })
+        .await
+    }
+
+    fn pause_tx() -> TypedTransaction {
+        TransactionRequest::new()
+            .to(Address::repeat_byte(0x11))
+            .data(vec![0x84, 0x56, 0xcb, 0x59])
+            .nonce(7)
+            .gas(100_000)
+            .gas_price(1_000_000_000)
+            .into()
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn remote_signatures_are_checked() {
+        let server = remote_signer(KEY, |_| {});
+        let signer = connect(&server).await.unwrap();
+        assert_eq!(signer.chain_id(), 5);
+
+        // signed for the chain the signer is on, exactly as a local wallet with the same key would
+        let local = WatchtowerSigner::from_key(KEY).unwrap().with_chain_id(5u64);
+        let mut tx = pause_tx();
+        tx.set_chain_id(5);
+        assert_eq!(
+            signer.sign_transaction(&pause_tx()).await.unwrap(),
+            local.sign_transaction(&tx).await.unwrap()
+        );
+        assert_eq!(
+            signer.sign_message(b"pause").await.unwrap(),
+            local.sign_message(b"pause").await.unwrap()
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn remote_signatures_from_other_keys_are_rejected() {
+        let server = remote_signer(OTHER_KEY, |_| {});
+        let signer = connect(&server).await.unwrap();
+        assert!(signer.sign_transaction(&pause_tx()).await.is_err());
+        assert!(signer.sign_message(b"pause").await.is_err());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn remote_signatures_of_other_transactions_are_rejected() {
+        let server = remote_signer(KEY, |tx| {
+            tx.set_to(Address::repeat_byte(0x22));
+        });
+        let signer = connect(&server).await.unwrap();
+        assert!(signer.sign_transaction(&pause_tx()).await.is_err());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn remote_signer_must_hold_the_key() {
+        let server = remote_signer(KEY, |_| {});
+        let address = OTHER_KEY.parse::<Wallet<SigningKey>>().unwrap().address();
+        let config = RemoteSignerConfig {
+            url: server.url.clone(),
+            address: format!("{address:?}"),
+            timeout: 10,
+        };
+        assert!(WatchtowerSigner::remote(&config).await.is_err());
+    }
+}
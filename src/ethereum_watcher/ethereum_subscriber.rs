@@ -0,0 +1,205 @@
+use super::{WEBSOCKET_MAX_RECONNECT_DELAY, WEBSOCKET_RECONNECT_DELAY};
+use crate::alerts::{AlertLevel, WatchtowerAlerts};
+use crate::WatchtowerConfig;
+
+use anyhow::Result;
+use ethers::abi::Address;
+use ethers::providers::{Middleware, Provider, StreamExt, Ws};
+use ethers::types::{Filter, H160};
+use std::cmp::min;
+use std::str::FromStr;
+use tokio::sync::watch;
+
+#[derive(Clone, Debug)]
+pub struct EthereumSubscriber {
+    ws_url: String,
+    addresses: Vec<H160>,
+    log_tag: String,
+}
+
+impl EthereumSubscriber {
+    pub fn new(config: &WatchtowerConfig) -> Result<Option<Self>> {
+        let ws_url = match &config.ethereum_ws {
+            Some(ws_url) => ws_url.clone(),
+            None => return Ok(None),
+        };
+        let addresses = vec![
+            Address::from_str(&config.state_contract_address)?,
+            Address::from_str(&config.portal_contract_address)?,
+            Address::from_str(&config.gateway_contract_address)?,
+        ];
+
+        Ok(Some(EthereumSubscriber {
+            ws_url,
+            addresses,
+            log_tag: config.log_tag(),
+        }))
+    }
+
+    // starts a thread that follows new heads and bridge contract logs over the websocket. The returned receiver holds
+    // the block number of the latest event, or None while the websocket is disconnected. The thread (and its
+    // websocket) stops once every receiver has been dropped, such as when the watcher is restarted
+    pub fn start(self, alerts: WatchtowerAlerts, alert_level: AlertLevel) -> watch::Receiver<Option<u64>> {
+        let (tx, rx) = watch::channel::<Option<u64>>(None);
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = self.run(&tx, &alerts, &alert_level) => {}
+                _ = tx.closed() => {}
+            }
+        });
+
+        rx
+    }
+
+    async fn run(&self, tx: &watch::Sender<Option<u64>>, alerts: &WatchtowerAlerts, alert_level: &AlertLevel) {
+        let mut reconnect_delay = WEBSOCKET_RECONNECT_DELAY;
+        let mut disconnect_reported = false;
+        loop {
+            let result = match Provider::<Ws>::connect(self.ws_url.as_str()).await {
+                Ok(provider) => {
+                    alerts.alert(String::from("Connected to ethereum websocket."), AlertLevel::Info);
+                    disconnect_reported = false;
+                    reconnect_delay = WEBSOCKET_RECONNECT_DELAY;
+                    self.follow(&provider, tx).await
+                }
+                Err(e) => Err(anyhow::anyhow!("{e}")),
+            };
+
+            // fall back to http polling until the websocket comes back
+            tx.send_replace(None);
+            if let Err(e) = result {
+                if !disconnect_reported {
+                    alerts.alert(
+                        format!("Ethereum websocket disconnected, falling back to HTTP polling: {e}"),
+                        alert_level.clone(),
+                    );
+                    disconnect_reported = true;
+                }
+            }
+            tokio::time::sleep(reconnect_delay).await;
+            reconnect_delay = min(reconnect_delay * 2, WEBSOCKET_MAX_RECONNECT_DELAY);
+        }
+    }
+
+    async fn follow(&self, provider: &Provider<Ws>, tx: &watch::Sender<Option<u64>>) -> Result<()> {
+        let mut blocks = provider.subscribe_blocks().await?;
+        let filter = Filter::new().address(self.addresses.clone());
+        let mut logs = provider.subscribe_logs(&filter).await?;
+        loop {
+            tokio::select! {
+                block = blocks.next() => match block {
+                    Some(block) => {
+                        if let Some(block_num) = block.number {
+                            tx.send_replace(Some(block_num.as_u64()));
+                        }
+                    }
+                    None => return Err(anyhow::anyhow!("New block subscription closed")),
+                },
+                log = logs.next() => match log {
+                    Some(log) => {
+                        log::info!(
+                            "{}Bridge contract event from {:?} in block {:?}",
+                            self.log_tag,
+                            log.address,
+                            log.block_number
+                        );
+                        if let Some(block_num) = log.block_number {
+                            tx.send_replace(Some(block_num.as_u64()));
+                        }
+                    }
+                    None => return Err(anyhow::anyhow!("Bridge contract log subscription closed")),
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::WatchtowerMetrics;
+    use crate::test_utils::test_config;
+    use futures::SinkExt;
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tokio::sync::Notify;
+    use tokio_tungstenite::tungstenite::Message;
+
+    // a websocket endpoint that accepts the subscriptions of each connection and announces one new head on it. The
+    // first connection is dropped (without a close frame, as when a node goes away) once `drop_connection` is notified
+    async fn start_endpoint(block_nums: Vec<u64>, drop_connection: Arc<Notify>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut connections = Vec::new();
+            for (connection, block_num) in block_nums.into_iter().enumerate() {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+                let mut new_heads = None;
+                for subscription_id in 1..=2 {
+                    let request: Value = match ws.next().await {
+                        Some(Ok(Message::Text(text))) => serde_json::from_str(&text).unwrap(),
+                        _ => return,
+                    };
+                    if request["params"][0] == "newHeads" {
+                        new_heads = Some(subscription_id);
+                    }
+                    let response =
+                        json!({ "jsonrpc": "2.0", "id": request["id"], "result": format!("{subscription_id:#x}") });
+                    ws.send(Message::Text(response.to_string())).await.unwrap();
+                }
+                let notification = json!({
+                    "jsonrpc": "2.0",
+                    "method": "eth_subscription",
+                    "params": {
+                        "subscription": format!("{:#x}", new_heads.unwrap()),
+                        "result": { "number": format!("{block_num:#x}") }
+                    }
+                });
+                ws.send(Message::Text(notification.to_string())).await.unwrap();
+                if connection == 0 {
+                    drop_connection.notified().await;
+                    drop(ws);
+                } else {
+                    connections.push(ws);
+                }
+            }
+            // keep the last connections open
+            std::future::pending::<()>().await;
+        });
+        url
+    }
+
+    async fn next_event(events: &mut watch::Receiver<Option<u64>>) -> Option<u64> {
+        tokio::time::timeout(Duration::from_secs(10), events.changed())
+            .await
+            .expect("no websocket event")
+            .unwrap();
+        *events.borrow()
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn dropped_subscriptions_fall_back_to_polling() {
+        let drop_connection = Arc::new(Notify::new());
+        let mut config = test_config();
+        config.ethereum_ws = Some(start_endpoint(vec![16, 17], drop_connection.clone()).await);
+        let alerts = WatchtowerAlerts::new(&config, WatchtowerMetrics::new()).unwrap();
+        let subscriber = EthereumSubscriber::new(&config).unwrap().unwrap();
+        let mut events = subscriber.start(alerts.for_check("websocket"), AlertLevel::Warn);
+
+        assert_eq!(next_event(&mut events).await, Some(16));
+        assert!(alerts.get_active_alerts().is_empty());
+
+        // checks triggered by the events run right away when the subscription drops, and poll until it is back
+        drop_connection.notify_one();
+        assert_eq!(next_event(&mut events).await, None);
+        let active_alerts = alerts.get_active_alerts();
+        assert_eq!(active_alerts.len(), 1);
+        assert_eq!(active_alerts[0].level, AlertLevel::Warn);
+        assert!(active_alerts[0].text.contains("falling back to HTTP polling"));
+
+        assert_eq!(next_event(&mut events).await, Some(17));
+    }
+}
@@ -1,15 +1,14 @@
-use super::{ETHEREUM_BLOCK_TIME, ETHEREUM_CONNECTION_RETRIES};
+use super::contract_pauser::ContractPauser;
+use super::ethereum_client::EthereumClient;
+use super::ethereum_signer::WatchtowerSigner;
+use super::ETHEREUM_CONNECTION_RETRIES;
 use crate::WatchtowerConfig;
 
 use anyhow::Result;
 use ethers::abi::Address;
-use ethers::prelude::k256::ecdsa::SigningKey;
-use ethers::prelude::{abigen, SignerMiddleware};
-use ethers::providers::{Http, Middleware, Provider};
-use ethers::signers::{Signer, Wallet};
+use ethers::prelude::abigen;
+use ethers::providers::{Middleware, Provider};
 use ethers::types::{Filter, H160, U256};
-use std::cmp::max;
-use std::convert::TryFrom;
 use std::ops::Mul;
 use std::str::FromStr;
 use std::sync::Arc;
@@ -18,33 +17,29 @@ abigen!(FuelMessagePortal, "./abi/FuelMessagePortal.json");
 
 #[derive(Clone, Debug)]
 pub struct PortalContract {
-    provider: Provider<Http>,
-    contract: FuelMessagePortal<SignerMiddleware<Provider<Http>, Wallet<SigningKey>>>,
+    provider: Provider<EthereumClient>,
+    pauser: ContractPauser,
     address: H160,
-    read_only: bool,
 }
 
 impl PortalContract {
-    pub async fn new(config: &WatchtowerConfig) -> Result<Self> {
-        // setup provider
-        let provider = Provider::<Http>::try_from(&config.ethereum_rpc)?;
-        let chain_id = provider.get_chainid().await?.as_u64();
-
-        // setup wallet
-        let mut read_only = false;
-        let key_str = match &config.ethereum_wallet_key {
-            Some(key) => key.clone(),
-            None => {
-                read_only = true;
-                String::from("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80")
-            }
-        };
-        let wallet: Wallet<SigningKey> = key_str.parse::<Wallet<SigningKey>>()?.with_chain_id(chain_id);
-
-        // setup contract
+    pub async fn new(
+        config: &WatchtowerConfig,
+        provider: Provider<EthereumClient>,
+        signers: &[WatchtowerSigner],
+    ) -> Result<Self> {
+        // setup a read only contract client, plus a separate one able to pause the contract for each configured account
         let address = Address::from_str(&config.portal_contract_address)?;
-        let client = SignerMiddleware::new(provider.clone(), wallet);
-        let contract = FuelMessagePortal::new(address, Arc::new(client));
+        let contract = FuelMessagePortal::new(address, Arc::new(provider.clone()));
+        let pauser = ContractPauser::new(
+            config,
+            "portal",
+            &contract,
+            contract.pause(),
+            contract.paused(),
+            signers,
+        )
+        .await?;
 
         // verify contract setup is valid
         let contract_result = contract.paused().call().await;
@@ -52,22 +47,19 @@ impl PortalContract {
             Err(_) => Err(anyhow::anyhow!("Invalid portal contract.")),
             Ok(_) => Ok(PortalContract {
                 provider,
-                contract,
+                pauser,
                 address,
-                read_only,
             }),
         }
     }
 
-    pub async fn get_amount_deposited(&self, timeframe: u32, latest_block_num: u64) -> Result<U256> {
-        let block_offset = timeframe as u64 / ETHEREUM_BLOCK_TIME;
-        let start_block = max(latest_block_num, block_offset) - block_offset;
-
+    pub async fn get_amount_deposited(&self, from_block: u64, to_block: u64) -> Result<U256> {
         //MessageSent(bytes32 indexed sender, bytes32 indexed recipient, uint256 indexed nonce, uint64 amount, bytes data)
         let filter = Filter::new()
             .address(self.address)
             .event("MessageSent(bytes32,bytes32,uint256,uint64,bytes)")
-            .from_block(start_block);
+            .from_block(from_block)
+            .to_block(to_block);
         for i in 0..ETHEREUM_CONNECTION_RETRIES {
             match self.provider.get_logs(&filter).await {
                 Ok(logs) => {
@@ -88,15 +80,13 @@ impl PortalContract {
         Ok(U256::zero())
     }
 
-    pub async fn get_amount_withdrawn(&self, timeframe: u32, latest_block_num: u64) -> Result<U256> {
-        let block_offset = timeframe as u64 / ETHEREUM_BLOCK_TIME;
-        let start_block = max(latest_block_num, block_offset) - block_offset;
-
+    pub async fn get_amount_withdrawn(&self, from_block: u64, to_block: u64) -> Result<U256> {
         //MessageRelayed(bytes32 indexed messageId, bytes32 indexed sender, bytes32 indexed recipient, uint64 amount)
         let filter = Filter::new()
             .address(self.address)
             .event("MessageRelayed(bytes32,bytes32,bytes32,uint64)")
-            .from_block(start_block);
+            .from_block(from_block)
+            .to_block(to_block);
         for i in 0..ETHEREUM_CONNECTION_RETRIES {
             match self.provider.get_logs(&filter).await {
                 Ok(logs) => {
@@ -117,16 +107,12 @@ impl PortalContract {
         Ok(U256::zero())
     }
 
-    pub async fn pause(&self) -> Result<()> {
-        if self.read_only {
-            return Err(anyhow::anyhow!("Ethereum account not configured."));
-        }
+    pub async fn is_paused(&self) -> Result<bool> {
+        self.pauser.is_paused().await
+    }
 
-        // TODO: implement alert on timeout and a gas escalator (https://github.com/gakonst/ethers-rs/blob/master/examples/middleware/examples/gas_escalator.rs)
-        let result = self.contract.pause().call().await;
-        match result {
-            Err(e) => Err(anyhow::anyhow!("Failed to pause portal contract: {}", e)),
-            Ok(_) => Ok(()),
-        }
+    // pauses the contract from any of the configured accounts
+    pub fn get_pauser(&self) -> &ContractPauser {
+        &self.pauser
     }
 }
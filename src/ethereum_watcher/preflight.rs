@@ -0,0 +1,168 @@
+use super::ethereum_client::EthereumClient;
+
+use anyhow::Result;
+use ethers::abi::{self, Abi, ParamType, Token};
+use ethers::providers::{HttpClientError, Middleware, Provider, ProviderError};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Bytes, U256};
+use serde_json::Value;
+use std::str::FromStr;
+
+// selectors of the revert data solidity itself produces, Error(string) and Panic(uint256)
+pub static ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+pub static PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+// estimates the gas of the transaction from its sender before it is sent, so a transaction bound to revert never is.
+// Returns the estimate, or the reason it would revert decoded with the contract abi
+pub async fn preflight(provider: &Provider<EthereumClient>, abi: &Abi, tx: &TypedTransaction) -> Result<U256> {
+    match provider.estimate_gas(tx, None).await {
+        Ok(gas) => Ok(gas),
+        Err(e) => match get_revert_data(&e) {
+            Some(data) => Err(anyhow::anyhow!("{}", decode_revert(abi, &data))),
+            None => Err(anyhow::anyhow!("{}", e)),
+        },
+    }
+}
+
+// describes revert data, pointing out the reverts a pause is known to run into
+pub fn decode_revert(abi: &Abi, data: &[u8]) -> String {
+    if data.is_empty() {
+        return String::from("reverted without a reason (check the proxy points to a valid implementation)");
+    }
+    if data.len() >= 4 {
+        let (selector, args) = data.split_at(4);
+        if selector == ERROR_SELECTOR {
+            if let Ok(mut tokens) = abi::decode(&[ParamType::String], args) {
+                if let Some(Token::String(reason)) = tokens.pop() {
+                    return explain_reason(reason);
+                }
+            }
+        }
+        if selector == PANIC_SELECTOR {
+            if let Ok(mut tokens) = abi::decode(&[ParamType::Uint(256)], args) {
+                if let Some(Token::Uint(code)) = tokens.pop() {
+                    return format!("panicked with code {code:#x}");
+                }
+            }
+        }
+        for error in abi.errors() {
+            if error.signature()[..4] == *selector {
+                if let Ok(tokens) = error.decode(args) {
+                    let args: Vec<String> = tokens.iter().map(|token| token.to_string()).collect();
+                    return format!("reverted with {}({})", error.name, args.join(", "));
+                }
+            }
+        }
+    }
+    format!("reverted with unknown error data {}", Bytes::from(data.to_vec()))
+}
+
+fn explain_reason(reason: String) -> String {
+    if reason.starts_with("AccessControl:") {
+        format!("missing role ({reason})")
+    } else if reason == "Pausable: paused" {
+        format!("already paused ({reason})")
+    } else if reason.starts_with("ERC1967") || reason.starts_with("Initializable") || reason.contains("delegatecall") {
+        format!("proxy misconfigured ({reason})")
+    } else {
+        format!("reverted with \"{reason}\"")
+    }
+}
+
+// nodes return the revert data of a failed call in the json-rpc error, either as is or nested under another data field
+fn get_revert_data(error: &ProviderError) -> Option<Bytes> {
+    let error = match error {
+        ProviderError::JsonRpcClientError(error) => error.downcast_ref::<HttpClientError>()?,
+        _ => return None,
+    };
+    let data = match error {
+        HttpClientError::JsonRpcError(error) => error.data.as_ref()?,
+        _ => return None,
+    };
+    let data = match data {
+        Value::String(data) => data,
+        Value::Object(fields) => fields.get("data")?.as_str()?,
+        _ => return None,
+    };
+    Bytes::from_str(data).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::Address;
+    use serde_json::json;
+
+    fn error_data(reason: &str) -> Vec<u8> {
+        let mut data = ERROR_SELECTOR.to_vec();
+        data.extend(abi::encode(&[Token::String(reason.to_string())]));
+        data
+    }
+
+    fn rpc_error(error: Value) -> ProviderError {
+        HttpClientError::JsonRpcError(serde_json::from_value(error).unwrap()).into()
+    }
+
+    #[test]
+    fn known_reasons_are_explained() {
+        let abi = Abi::default();
+        let reason = "AccessControl: account 0x01 is missing role 0x02";
+        assert_eq!(
+            decode_revert(&abi, &error_data(reason)),
+            format!("missing role ({reason})")
+        );
+        assert_eq!(
+            decode_revert(&abi, &error_data("Pausable: paused")),
+            "already paused (Pausable: paused)"
+        );
+        assert_eq!(
+            decode_revert(&abi, &error_data("Initializable: contract is not initialized")),
+            "proxy misconfigured (Initializable: contract is not initialized)"
+        );
+        assert_eq!(decode_revert(&abi, &error_data("nope")), "reverted with \"nope\"");
+    }
+
+    #[test]
+    fn panics_and_empty_reverts_are_described() {
+        let abi = Abi::default();
+        let mut data = PANIC_SELECTOR.to_vec();
+        data.extend(abi::encode(&[Token::Uint(U256::from(0x11))]));
+        assert_eq!(decode_revert(&abi, &data), "panicked with code 0x11");
+        assert!(decode_revert(&abi, &[]).starts_with("reverted without a reason"));
+        assert_eq!(
+            decode_revert(&abi, &[0xde, 0xad, 0xbe, 0xef]),
+            "reverted with unknown error data 0xdeadbeef"
+        );
+    }
+
+    #[test]
+    fn custom_errors_are_decoded_with_the_abi() {
+        let abi: Abi = serde_json::from_value(json!([{
+            "type": "error",
+            "name": "Unauthorized",
+            "inputs": [{ "name": "account", "type": "address" }]
+        }]))
+        .unwrap();
+        let error = abi.errors().next().unwrap();
+        let mut data = error.signature()[..4].to_vec();
+        data.extend(abi::encode(&[Token::Address(Address::repeat_byte(1))]));
+
+        assert_eq!(
+            decode_revert(&abi, &data),
+            format!(
+                "reverted with Unauthorized({})",
+                Token::Address(Address::repeat_byte(1))
+            )
+        );
+    }
+
+    #[test]
+    fn revert_data_is_read_from_rpc_errors() {
+        let plain = rpc_error(json!({ "code": 3, "message": "execution reverted", "data": "0x01" }));
+        assert_eq!(get_revert_data(&plain), Some(Bytes::from(vec![1])));
+        let nested = rpc_error(json!({ "code": -32000, "message": "reverted", "data": { "data": "0x02" } }));
+        assert_eq!(get_revert_data(&nested), Some(Bytes::from(vec![2])));
+        let no_data = rpc_error(json!({ "code": -32000, "message": "nonce too low" }));
+        assert_eq!(get_revert_data(&no_data), None);
+    }
+}
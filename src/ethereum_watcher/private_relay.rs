@@ -0,0 +1,282 @@
+use super::ethereum_client::EthereumClient;
+use super::ethereum_signer::WatchtowerSigner;
+use crate::config::PrivateRelayMethod;
+use crate::WatchtowerConfig;
+
+use anyhow::Result;
+use ethers::prelude::SignerMiddleware;
+use ethers::providers::{Middleware, Provider};
+use ethers::signers::Signer;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Bytes, H256, U64};
+use ethers::utils::keccak256;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+pub static PRIVATE_RELAY_POLL_INTERVAL: Duration = Duration::from_millis(2000);
+
+// sends transactions through a private relay (flashbots style) instead of the public mempool, so a pause does not
+// tip off an attacker before it lands. If it has not landed after the configured number of blocks, the same signed
+// transaction is broadcast publicly
+#[derive(Clone, Debug)]
+pub struct PrivateRelay {
+    client: Client,
+    url: String,
+    method: PrivateRelayMethod,
+    fallback_blocks: u64,
+    log_tag: String,
+}
+
+impl PrivateRelay {
+    pub fn new(config: &WatchtowerConfig) -> Result<Option<Self>> {
+        let relay_config = match &config.ethereum_private_relay {
+            Some(relay_config) => relay_config,
+            None => return Ok(None),
+        };
+        Ok(Some(PrivateRelay {
+            client: Client::builder()
+                .timeout(Duration::from_secs(relay_config.timeout))
+                .build()?,
+            url: relay_config.url.clone(),
+            method: relay_config.method.clone(),
+            fallback_blocks: relay_config.fallback_blocks,
+            log_tag: config.log_tag(),
+        }))
+    }
+
+    // signs the transaction and submits it to the relay, falling back to a public broadcast. Returns the hash of the
+    // transaction once it has been mined or broadcast publicly
+    pub async fn send(
+        &self,
+        client: &SignerMiddleware<Provider<EthereumClient>, WatchtowerSigner>,
+        mut tx: TypedTransaction,
+    ) -> Result<H256> {
+        client.fill_transaction(&mut tx, None).await?;
+        let signature = client.signer().sign_transaction(&tx).await?;
+        let raw_tx = tx.rlp_signed(&signature);
+        let tx_hash = H256::from(keccak256(&raw_tx));
+        let provider = client.inner();
+
+        // keep the transaction with the relay until it lands or the fallback block is reached. Bundles only target
+        // a single block, so they are sent again for every new block
+        let fallback_block = provider.get_block_number().await? + self.fallback_blocks;
+        let mut target_block = U64::zero();
+        loop {
+            let block_number = provider.get_block_number().await?;
+            if provider.get_transaction_receipt(tx_hash).await?.is_some() {
+                return Ok(tx_hash);
+            }
+            if block_number >= fallback_block {
+                break;
+            }
+            if block_number >= target_block {
+                target_block = block_number + 1;
+                if let Err(e) = self
+                    .submit(client.signer(), &raw_tx, target_block, fallback_block)
+                    .await
+                {
+                    log::warn!(
+                        "{}Failed to send transaction {:?} to the private relay, broadcasting it publicly: {}",
+                        self.log_tag,
+                        tx_hash,
+                        e
+                    );
+                    break;
+                }
+                if self.method == PrivateRelayMethod::PrivateTransaction {
+                    // the relay keeps trying by itself until the fallback block
+                    target_block = fallback_block;
+                }
+            }
+            tokio::time::sleep(PRIVATE_RELAY_POLL_INTERVAL).await;
+        }
+
+        log::warn!(
+            "{}Transaction {:?} did not land through the private relay, broadcasting it publicly.",
+            self.log_tag,
+            tx_hash
+        );
+        if let Err(e) = provider.send_raw_transaction(raw_tx).await {
+            // the private transaction may have landed in the meantime
+            if provider.get_transaction_receipt(tx_hash).await?.is_none() {
+                return Err(anyhow::anyhow!("Failed to broadcast transaction {:?}: {}", tx_hash, e));
+            }
+        }
+        Ok(tx_hash)
+    }
+
+    async fn submit(&self, signer: &WatchtowerSigner, raw_tx: &Bytes, target_block: U64, max_block: U64) -> Result<()> {
+        let (method, params) = match self.method {
+            PrivateRelayMethod::PrivateTransaction => (
+                "eth_sendPrivateTransaction",
+                json!([{ "tx": raw_tx, "maxBlockNumber": max_block }]),
+            ),
+            PrivateRelayMethod::Bundle => (
+                "eth_sendBundle",
+                json!([{ "txs": [raw_tx], "blockNumber": target_block }]),
+            ),
+        };
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }).to_string();
+
+        // relays identify the sender by a signature of the request body
+        let body_hash = format!("{:?}", H256::from(keccak256(body.as_bytes())));
+        let signature = signer.sign_message(body_hash).await?;
+        let response = self
+            .client
+            .post(&self.url)
+            .header(CONTENT_TYPE, "application/json")
+            .header(
+                "X-Flashbots-Signature",
+                format!("{:?}:0x{}", signer.address(), signature),
+            )
+            .body(body)
+            .send()
+            .await?;
+        let status = response.status();
+        let text = response.text().await?;
+        if !status.is_success() {
+            return Err(anyhow::anyhow!("{} responded {}: {}", method, status, text));
+        }
+        let response: Value = serde_json::from_str(&text)?;
+        match response.get("error") {
+            Some(error) => Err(anyhow::anyhow!("{} failed: {}", method, error)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PrivateRelayConfig;
+    use crate::test_utils::{test_config, MockServer};
+    use ethers::types::{Address, Signature, TransactionRequest};
+    use std::str::FromStr;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    static KEY: &str = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    // a node producing a block every time it is polled for the receipt, where the transaction sent through the relay
+    // lands by the second poll if `lands` is set
+    fn node(lands: bool) -> MockServer {
+        let block_number = Arc::new(AtomicU64::new(100));
+        MockServer::json_rpc(move |method, params| match method {
+            "eth_blockNumber" => Ok(json!(U64::from(block_number.load(Ordering::SeqCst)))),
+            "eth_getTransactionReceipt" => match block_number.fetch_add(1, Ordering::SeqCst) > 100 && lands {
+                true => Ok(json!({
+                    "transactionHash": params[0],
+                    "transactionIndex": "0x0",
+                    "blockHash": H256::repeat_byte(0x11),
+                    "blockNumber": "0x65",
+                    "from": Address::zero(),
+                    "cumulativeGasUsed": "0x5208",
+                    "gasUsed": "0x5208",
+                    "logs": [],
+                    "logsBloom": format!("0x{}", "00".repeat(256)),
+                    "status": "0x1",
+                })),
+                false => Ok(Value::Null),
+            },
+            "eth_sendRawTransaction" => Ok(json!(H256::from(keccak256(
+                serde_json::from_value::<Bytes>(params[0].clone()).unwrap()
+            )))),
+            _ => Err(json!({ "code": -32601, "message": "method not found" })),
+        })
+    }
+
+    fn relay(accepts: bool) -> MockServer {
+        MockServer::json_rpc(move |_, _| match accepts {
+            true => Ok(json!(null)),
+            false => Err(json!({ "code": -32000, "message": "relay overloaded" })),
+        })
+    }
+
+    // sends a pause through the relay from a signer on chain 5
+    async fn send(node: &MockServer, relay: &MockServer) -> Result<H256> {
+        let mut config = test_config();
+        config.ethereum_rpc = vec![node.url.clone()];
+        config.ethereum_rpc_quorum = 1;
+        config.ethereum_private_relay = Some(PrivateRelayConfig {
+            url: relay.url.clone(),
+            method: PrivateRelayMethod::PrivateTransaction,
+            fallback_blocks: 1,
+            timeout: 10,
+        });
+        let private_relay = PrivateRelay::new(&config).unwrap().unwrap();
+        let provider = Provider::new(EthereumClient::new(&config).unwrap());
+        let signer = WatchtowerSigner::from_key(KEY).unwrap().with_chain_id(5u64);
+        let client = SignerMiddleware::new(provider, signer);
+        let tx = TransactionRequest::new()
+            .to(Address::repeat_byte(0x22))
+            .data(vec![0x84, 0x56, 0xcb, 0x59])
+            .nonce(7)
+            .gas(100_000)
+            .gas_price(1_000_000_000)
+            .chain_id(5);
+        private_relay.send(&client, tx.into()).await
+    }
+
+    // the signed transactions the relay was asked to keep private, after checking each request was signed by the
+    // sender
+    fn relayed_transactions(relay: &MockServer) -> Vec<Bytes> {
+        let sender = WatchtowerSigner::from_key(KEY).unwrap().address();
+        relay
+            .requests()
+            .iter()
+            .map(|request| {
+                let header = request.headers["X-Flashbots-Signature"].to_str().unwrap();
+                let (address, signature) = header.split_once(':').unwrap();
+                let body_hash = format!("{:?}", H256::from(keccak256(request.body.as_bytes())));
+                let signature = Signature::from_str(signature).unwrap();
+                assert_eq!(Address::from_str(address).unwrap(), sender);
+                assert_eq!(signature.recover(body_hash).unwrap(), sender);
+                serde_json::from_value(request.json()["params"][0]["tx"].clone()).unwrap()
+            })
+            .collect()
+    }
+
+    fn broadcast_transactions(node: &MockServer) -> Vec<Value> {
+        node.rpc_requests("eth_sendRawTransaction")
+            .iter()
+            .map(|request| request["params"][0].clone())
+            .collect()
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn landed_transaction_is_never_broadcast() {
+        let (node, relay) = (node(true), relay(true));
+        let tx_hash = send(&node, &relay).await.unwrap();
+
+        let relayed = relayed_transactions(&relay);
+        assert_eq!(relayed.len(), 1);
+        assert_eq!(tx_hash, H256::from(keccak256(&relayed[0])));
+        assert!(broadcast_transactions(&node).is_empty());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn same_transaction_is_broadcast_once_fallback_block_is_reached() {
+        let (node, relay) = (node(false), relay(true));
+        let tx_hash = send(&node, &relay).await.unwrap();
+
+        let relayed = relayed_transactions(&relay);
+        assert_eq!(relayed.len(), 1);
+        assert_eq!(tx_hash, H256::from(keccak256(&relayed[0])));
+        assert_eq!(broadcast_transactions(&node), vec![json!(relayed[0])]);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn rejected_transaction_is_broadcast_right_away() {
+        let (node, relay) = (node(false), relay(false));
+        let started = std::time::Instant::now();
+        let tx_hash = send(&node, &relay).await.unwrap();
+
+        let relayed = relayed_transactions(&relay);
+        assert_eq!(relayed.len(), 1);
+        assert_eq!(broadcast_transactions(&node), vec![json!(relayed[0])]);
+        assert_eq!(tx_hash, H256::from(keccak256(&relayed[0])));
+        assert!(started.elapsed() < PRIVATE_RELAY_POLL_INTERVAL);
+    }
+}
@@ -0,0 +1,392 @@
+use super::ethereum_client::EthereumClient;
+use super::ethereum_signer::WatchtowerSigner;
+use super::preflight::preflight;
+use crate::config::SafeConfig;
+
+use anyhow::Result;
+use ethers::abi::{Abi, Address};
+use ethers::prelude::abigen;
+use ethers::providers::Provider;
+use ethers::signers::Signer;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Bytes, TransactionRequest, H160, H256, U256};
+use ethers::utils::to_checksum;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+abigen!(
+    GnosisSafe,
+    r#"[
+        function nonce() external view returns (uint256)
+        function isOwner(address owner) external view returns (bool)
+        function getTransactionHash(address to, uint256 value, bytes data, uint8 operation, uint256 safeTxGas, uint256 baseGas, uint256 gasPrice, address gasToken, address refundReceiver, uint256 _nonce) external view returns (bytes32)
+    ]"#
+);
+
+pub static SAFE_TX_ORIGIN: &str = "fuel-canary-watchtower";
+pub static SAFE_QUEUE_LIMIT: usize = 100;
+
+// a Safe multisig holding the pauser role. Pauses are not sent directly but proposed to the Safe transaction service,
+// signed by one of the owners, for the other owners to confirm and execute
+#[derive(Clone, Debug)]
+pub struct SafeContract {
+    provider: Provider<EthereumClient>,
+    contract: GnosisSafe<Provider<EthereumClient>>,
+    owners: Vec<WatchtowerSigner>,
+    client: Client,
+    transaction_service_url: String,
+    address: H160,
+}
+
+// a transaction proposed to the Safe that has not been executed yet
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+struct QueuedTransaction {
+    to: H160,
+    data: Option<Bytes>,
+    nonce: u64,
+    safe_tx_hash: H256,
+    origin: Option<Value>,
+}
+
+#[derive(Deserialize, Debug)]
+struct QueuedTransactions {
+    results: Vec<QueuedTransaction>,
+}
+
+impl QueuedTransaction {
+    fn is_watchtower_proposal(&self) -> bool {
+        match &self.origin {
+            Some(origin) => origin.to_string().contains(SAFE_TX_ORIGIN),
+            None => false,
+        }
+    }
+}
+
+impl SafeContract {
+    pub async fn new(
+        config: &SafeConfig,
+        provider: Provider<EthereumClient>,
+        signers: &[WatchtowerSigner],
+    ) -> Result<Self> {
+        let address = Address::from_str(&config.address)?;
+        let contract = GnosisSafe::new(address, Arc::new(provider.clone()));
+
+        // verify contract setup is valid, and keep the accounts able to sign for the Safe (in the order they are listed)
+        if contract.nonce().call().await.is_err() {
+            return Err(anyhow::anyhow!("Invalid Safe contract."));
+        }
+        let mut owners = vec![];
+        for signer in signers {
+            if contract.is_owner(signer.address()).call().await? {
+                owners.push(signer.clone());
+            }
+        }
+        if owners.is_empty() {
+            return Err(anyhow::anyhow!(
+                "None of the configured ethereum accounts is an owner of Safe {:?}.",
+                address
+            ));
+        }
+
+        Ok(SafeContract {
+            provider,
+            contract,
+            owners,
+            client: Client::builder().timeout(Duration::from_secs(config.timeout)).build()?,
+            transaction_service_url: config.transaction_service_url.trim_end_matches('/').to_string(),
+            address,
+        })
+    }
+
+    pub fn get_address(&self) -> H160 {
+        self.address
+    }
+
+    // the configured accounts that sign proposals, in order of preference
+    pub fn get_owner_addresses(&self) -> Vec<H160> {
+        self.owners.iter().map(|owner| owner.address()).collect()
+    }
+
+    // estimates the gas of the call from the Safe without sending any transaction, to check it would go through once
+    // executed (and why not, decoded with the abi of the contract called)
+    pub async fn simulate(&self, to: H160, data: Bytes, abi: &Abi) -> Result<()> {
+        let tx: TypedTransaction = TransactionRequest::new().from(self.address).to(to).data(data).into();
+        preflight(&self.provider, abi, &tx).await?;
+        Ok(())
+    }
+
+    // proposes the call to the transaction service, signed by the first owner that gets it accepted, and returns the
+    // safe tx hash the other owners confirm along with the owner that signed it (none if the same call was already
+    // proposed and is still pending). The proposal takes the nonce of the next transaction to be executed, so it can
+    // replace whatever else is queued, skipping the nonces taken by pauses proposed before it that are still pending
+    pub async fn propose(&self, to: H160, data: Bytes) -> Result<(H256, Option<H160>)> {
+        let next_nonce = self.contract.nonce().call().await?;
+        let queued = self.get_queued_transactions(next_nonce).await?;
+        if let Some(pending) = queued
+            .iter()
+            .find(|queued| queued.to == to && queued.data.as_ref() == Some(&data))
+        {
+            return Ok((pending.safe_tx_hash, None));
+        }
+        let mut nonce = next_nonce;
+        while queued
+            .iter()
+            .any(|queued| queued.is_watchtower_proposal() && U256::from(queued.nonce) == nonce)
+        {
+            nonce += U256::one();
+        }
+
+        let safe_tx_hash = H256::from(
+            self.contract
+                .get_transaction_hash(
+                    to,
+                    U256::zero(),
+                    data.clone(),
+                    0,
+                    U256::zero(),
+                    U256::zero(),
+                    U256::zero(),
+                    Address::zero(),
+                    Address::zero(),
+                    nonce,
+                )
+                .call()
+                .await?,
+        );
+
+        let mut errors = vec![];
+        for owner in &self.owners {
+            match self.send_proposal(owner, to, &data, nonce, safe_tx_hash).await {
+                Ok(_) => return Ok((safe_tx_hash, Some(owner.address()))),
+                Err(e) => errors.push(format!("{:?}: {}", owner.address(), e)),
+            }
+        }
+        Err(anyhow::anyhow!(
+            "Failed to propose Safe transaction {:?} from every owner: {}",
+            safe_tx_hash,
+            errors.join(" ")
+        ))
+    }
+
+    // the transactions proposed from the given nonce on that are still waiting to be executed
+    async fn get_queued_transactions(&self, from_nonce: U256) -> Result<Vec<QueuedTransaction>> {
+        let url = format!(
+            "{}/api/v1/safes/{}/multisig-transactions/?executed=false&nonce__gte={}&limit={}",
+            self.transaction_service_url,
+            to_checksum(&self.address, None),
+            from_nonce,
+            SAFE_QUEUE_LIMIT
+        );
+        let response = self.client.get(url).send().await?;
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        if !status.is_success() {
+            return Err(anyhow::anyhow!("transaction service responded {}: {}", status, text));
+        }
+        let queued: QueuedTransactions = serde_json::from_str(&text)
+            .map_err(|e| anyhow::anyhow!("Invalid queued transactions from the transaction service: {}", e))?;
+        Ok(queued.results)
+    }
+
+    async fn send_proposal(
+        &self,
+        owner: &WatchtowerSigner,
+        to: H160,
+        data: &Bytes,
+        nonce: U256,
+        safe_tx_hash: H256,
+    ) -> Result<()> {
+        // signed as an eth_sign message, which the Safe tells apart from a signature of the raw hash by v + 4
+        let mut signature = owner.sign_message(safe_tx_hash.as_bytes()).await?;
+        signature.v += 4;
+
+        let body = json!({
+            "safe": to_checksum(&self.address, None),
+            "to": to_checksum(&to, None),
+            "value": "0",
+            "data": data,
+            "operation": 0,
+            "safeTxGas": "0",
+            "baseGas": "0",
+            "gasPrice": "0",
+            "gasToken": to_checksum(&Address::zero(), None),
+            "refundReceiver": to_checksum(&Address::zero(), None),
+            "nonce": nonce.as_u64(),
+            "contractTransactionHash": format!("{safe_tx_hash:?}"),
+            "sender": to_checksum(&owner.address(), None),
+            "signature": format!("0x{signature}"),
+            "origin": SAFE_TX_ORIGIN,
+        });
+        let url = format!(
+            "{}/api/v1/safes/{}/multisig-transactions/",
+            self.transaction_service_url,
+            to_checksum(&self.address, None)
+        );
+        let response = self
+            .client
+            .post(url)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body.to_string())
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!("transaction service responded {}: {}", status, text));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{test_config, MockServer};
+    use ethers::abi::{encode, Token};
+    use ethers::types::{Signature, U64};
+    use ethers::utils::keccak256;
+
+    static OWNER_KEY: &str = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+    static SAFE_ADDRESS: &str = "0x1111111111111111111111111111111111111111";
+
+    // an ethereum node and Safe transaction service in one, for a Safe at nonce 5 with the given transactions queued.
+    // Safe tx hashes are the hash of the getTransactionHash call
+    fn safe_endpoint(queued: Vec<Value>) -> MockServer {
+        MockServer::start(move |request| {
+            if request.path.starts_with("/api/v1/safes/") {
+                return match request.method.as_str() {
+                    "GET" => (200, json!({ "results": queued })),
+                    _ => (201, json!({})),
+                };
+            }
+            let request = request.json();
+            let result = match request["method"].as_str().unwrap_or_default() {
+                "eth_blockNumber" => json!(U64::from(100)),
+                "eth_call" => {
+                    let call: Bytes = serde_json::from_value(request["params"][0]["data"].clone()).unwrap();
+                    match &call[..4] {
+                        // nonce() and isOwner(address)
+                        [0xaf, 0xfe, 0xd0, 0xe0] => json!(Bytes::from(encode(&[Token::Uint(U256::from(5))]))),
+                        [0x2f, 0x54, 0xbf, 0x6e] => json!(Bytes::from(encode(&[Token::Bool(true)]))),
+                        _ => json!(Bytes::from(keccak256(&call).to_vec())),
+                    }
+                }
+                _ => json!(null),
+            };
+            (200, json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+        })
+    }
+
+    async fn safe(endpoint: &MockServer) -> (SafeContract, Provider<EthereumClient>) {
+        let mut config = test_config();
+        config.ethereum_rpc = vec![endpoint.url.clone()];
+        config.ethereum_rpc_quorum = 1;
+        let provider = Provider::new(EthereumClient::new(&config).unwrap());
+        let safe_config = SafeConfig {
+            address: String::from(SAFE_ADDRESS),
+            transaction_service_url: endpoint.url.clone(),
+            timeout: 10,
+        };
+        let owner = WatchtowerSigner::from_key(OWNER_KEY).unwrap();
+        let safe = SafeContract::new(&safe_config, provider.clone(), &[owner])
+            .await
+            .unwrap();
+        (safe, provider)
+    }
+
+    fn pause_call(contract: u8) -> (H160, Bytes) {
+        (H160::repeat_byte(contract), Bytes::from(vec![0x84, 0x56, 0xcb, 0x59]))
+    }
+
+    fn queued(contract: u8, nonce: u64, origin: &str) -> Value {
+        let (to, data) = pause_call(contract);
+        json!({
+            "to": to_checksum(&to, None),
+            "data": data,
+            "nonce": nonce,
+            "safeTxHash": H256::repeat_byte(contract),
+            "origin": origin,
+        })
+    }
+
+    // the proposals posted to the transaction service
+    fn proposals(endpoint: &MockServer) -> Vec<Value> {
+        endpoint
+            .requests()
+            .iter()
+            .filter(|request| request.method == "POST" && request.path.starts_with("/api/v1/safes/"))
+            .map(|request| request.json())
+            .collect()
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn proposal_is_signed_by_owner_for_next_nonce() {
+        let endpoint = safe_endpoint(vec![queued(0x33, 5, "some other app")]);
+        let (safe, provider) = safe(&endpoint).await;
+        let (to, data) = pause_call(0x22);
+
+        let (safe_tx_hash, owner) = safe.propose(to, data.clone()).await.unwrap();
+        let owner_address = WatchtowerSigner::from_key(OWNER_KEY).unwrap().address();
+        assert_eq!(owner, Some(owner_address));
+
+        // the nonce the Safe is at, replacing the transaction queued by someone else
+        let proposals = proposals(&endpoint);
+        assert_eq!(proposals.len(), 1);
+        assert_eq!(proposals[0]["nonce"], json!(5));
+        let contract = GnosisSafe::new(Address::from_str(SAFE_ADDRESS).unwrap(), Arc::new(provider));
+        let zero = Address::zero();
+        let call = contract
+            .get_transaction_hash(
+                to,
+                0.into(),
+                data,
+                0,
+                0.into(),
+                0.into(),
+                0.into(),
+                zero,
+                zero,
+                5.into(),
+            )
+            .calldata()
+            .unwrap();
+        assert_eq!(safe_tx_hash, H256::from(keccak256(&call)));
+        assert_eq!(proposals[0]["contractTransactionHash"], json!(safe_tx_hash));
+
+        // an eth_sign signature of the safe tx hash by the owner
+        let mut signature = Signature::from_str(proposals[0]["signature"].as_str().unwrap()).unwrap();
+        signature.v -= 4;
+        assert_eq!(signature.recover(safe_tx_hash.as_bytes()).unwrap(), owner_address);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn proposals_take_consecutive_nonces() {
+        // the pause of two other contracts, proposed by the watchtower earlier and still pending
+        let endpoint = safe_endpoint(vec![queued(0x33, 5, SAFE_TX_ORIGIN), queued(0x44, 6, SAFE_TX_ORIGIN)]);
+        let (safe, _) = safe(&endpoint).await;
+        let (to, data) = pause_call(0x22);
+
+        safe.propose(to, data).await.unwrap();
+        let proposals = proposals(&endpoint);
+        assert_eq!(proposals.len(), 1);
+        assert_eq!(proposals[0]["nonce"], json!(7));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn pending_proposal_is_not_proposed_again() {
+        let endpoint = safe_endpoint(vec![queued(0x22, 6, SAFE_TX_ORIGIN)]);
+        let (safe, _) = safe(&endpoint).await;
+        let (to, data) = pause_call(0x22);
+
+        let (safe_tx_hash, owner) = safe.propose(to, data).await.unwrap();
+        assert_eq!(safe_tx_hash, H256::repeat_byte(0x22));
+        assert_eq!(owner, None);
+        assert!(proposals(&endpoint).is_empty());
+    }
+}
@@ -1,14 +1,14 @@
+use super::contract_pauser::ContractPauser;
+use super::ethereum_client::EthereumClient;
+use super::ethereum_signer::WatchtowerSigner;
 use super::ETHEREUM_CONNECTION_RETRIES;
 use crate::WatchtowerConfig;
 
 use anyhow::Result;
 use ethers::abi::Address;
-use ethers::prelude::k256::ecdsa::SigningKey;
-use ethers::prelude::{abigen, SignerMiddleware};
-use ethers::providers::{Http, Middleware, Provider};
-use ethers::signers::{Signer, Wallet};
-use ethers::types::{Filter, H160};
-use std::convert::TryFrom;
+use ethers::prelude::abigen;
+use ethers::providers::{Middleware, Provider};
+use ethers::types::{Filter, H160, H256};
 use std::str::FromStr;
 use std::sync::Arc;
 
@@ -16,33 +16,23 @@ abigen!(FuelChainState, "./abi/FuelChainState.json");
 
 #[derive(Clone, Debug)]
 pub struct StateContract {
-    provider: Provider<Http>,
-    contract: FuelChainState<SignerMiddleware<Provider<Http>, Wallet<SigningKey>>>,
+    provider: Provider<EthereumClient>,
+    contract: FuelChainState<Provider<EthereumClient>>,
+    pauser: ContractPauser,
     address: H160,
-    read_only: bool,
 }
 
 impl StateContract {
-    pub async fn new(config: &WatchtowerConfig) -> Result<Self> {
-        // setup provider
-        let provider = Provider::<Http>::try_from(&config.ethereum_rpc)?;
-        let chain_id = provider.get_chainid().await?.as_u64();
-
-        // setup wallet
-        let mut read_only = false;
-        let key_str = match &config.ethereum_wallet_key {
-            Some(key) => key.clone(),
-            None => {
-                read_only = true;
-                String::from("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80")
-            }
-        };
-        let wallet: Wallet<SigningKey> = key_str.parse::<Wallet<SigningKey>>()?.with_chain_id(chain_id);
-
-        // setup contract
+    pub async fn new(
+        config: &WatchtowerConfig,
+        provider: Provider<EthereumClient>,
+        signers: &[WatchtowerSigner],
+    ) -> Result<Self> {
+        // setup a read only contract client, plus a separate one able to pause the contract for each configured account
         let address = Address::from_str(&config.state_contract_address)?;
-        let client = SignerMiddleware::new(provider.clone(), wallet);
-        let contract = FuelChainState::new(address, Arc::new(client));
+        let contract = FuelChainState::new(address, Arc::new(provider.clone()));
+        let pauser =
+            ContractPauser::new(config, "state", &contract, contract.pause(), contract.paused(), signers).await?;
 
         // verify contract setup is valid
         let contract_result = contract.paused().call().await;
@@ -51,28 +41,28 @@ impl StateContract {
             Ok(_) => Ok(StateContract {
                 provider,
                 contract,
+                pauser,
                 address,
-                read_only,
             }),
         }
     }
 
-    pub async fn get_latest_commits(&self, from_block: u64) -> Result<Vec<String>> {
+    pub async fn get_latest_commits(&self, from_block: u64, to_block: u64) -> Result<Vec<String>> {
         //CommitSubmitted(uint256 indexed commitHeight, bytes32 blockHash)
         let filter = Filter::new()
             .address(self.address)
             .event("CommitSubmitted(uint256,bytes32)")
-            .from_block(from_block);
+            .from_block(from_block)
+            .to_block(to_block);
         for i in 0..ETHEREUM_CONNECTION_RETRIES {
             match self.provider.get_logs(&filter).await {
                 Ok(logs) => {
-                    // TODO
-
-                    return Ok(vec![
-                        String::from("0xe605385d3c61bdb625d58b2c55999730d9916fd90b62ce4af0d143539c3a5cb9"),
-                        String::from("0x24246dd5331b1eb52588ae8c0082987b1eb679edc09a4b5da305e4ebe5425c5a"),
-                        String::from("0x3de6af85929f81780a443b8db918a37036b7fe1b2e46e32fe876250806ab739d"),
-                    ]);
+                    let mut commits = Vec::new();
+                    for log in logs {
+                        let commit = self.contract.commit_submitted_filter().parse_log(log)?;
+                        commits.push(format!("{:?}", H256::from(commit.block_hash)));
+                    }
+                    return Ok(commits);
                 }
                 Err(e) => {
                     if i == ETHEREUM_CONNECTION_RETRIES - 1 {
@@ -84,16 +74,58 @@ impl StateContract {
         Ok(vec![])
     }
 
-    pub async fn pause(&self) -> Result<()> {
-        if self.read_only {
-            return Err(anyhow::anyhow!("Ethereum account not configured."));
-        }
+    pub async fn is_paused(&self) -> Result<bool> {
+        self.pauser.is_paused().await
+    }
 
-        // TODO: implement alert on timeout and a gas escalator (https://github.com/gakonst/ethers-rs/blob/master/examples/middleware/examples/gas_escalator.rs)
-        let result = self.contract.pause().call().await;
-        match result {
-            Err(e) => Err(anyhow::anyhow!("Failed to pause state contract: {}", e)),
-            Ok(_) => Ok(()),
-        }
+    // pauses the contract from any of the configured accounts
+    pub fn get_pauser(&self) -> &ContractPauser {
+        &self.pauser
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{test_config, MockServer};
+    use ethers::contract::EthEvent;
+    use ethers::types::{Bytes, U64};
+    use serde_json::{json, Value};
+
+    fn commit_log(address: H160, commit_height: u64, block_hash: H256) -> Value {
+        json!({
+            "address": address,
+            "topics": [CommitSubmittedFilter::signature(), H256::from_low_u64_be(commit_height)],
+            "data": Bytes::from(block_hash.as_bytes().to_vec()),
+        })
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn latest_commits_are_decoded_from_logs() {
+        let mut config = test_config();
+        let address = Address::from_str(&config.state_contract_address).unwrap();
+        let logs = json!([
+            commit_log(address, 1, H256::repeat_byte(0x11)),
+            commit_log(address, 2, H256::repeat_byte(0x22)),
+        ]);
+        let endpoint = MockServer::json_rpc(move |method, _| match method {
+            "eth_blockNumber" => Ok(json!(U64::from(100))),
+            "eth_call" => Ok(json!(Bytes::from(vec![0u8; 32]))),
+            "eth_getLogs" => Ok(logs.clone()),
+            _ => Err(json!({ "code": -32601, "message": "method not found" })),
+        });
+        config.ethereum_rpc = vec![endpoint.url.clone()];
+        config.ethereum_rpc_quorum = 1;
+        let provider = Provider::new(EthereumClient::new(&config).unwrap());
+        let state_contract = StateContract::new(&config, provider, &[]).await.unwrap();
+
+        let commits = state_contract.get_latest_commits(0, 100).await.unwrap();
+        assert_eq!(
+            commits,
+            vec![
+                format!("{:?}", H256::repeat_byte(0x11)),
+                format!("{:?}", H256::repeat_byte(0x22))
+            ]
+        );
     }
 }
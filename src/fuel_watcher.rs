@@ -1,11 +1,15 @@
 use crate::alerts::{AlertLevel, WatchtowerAlerts};
-use crate::ethereum_actions::WatchtowerEthereumActions;
+use crate::clients::WatchtowerClients;
+use crate::config::{BlockProductionAlert, GenericAlert, WithdrawAlert};
+use crate::ethereum_actions::{EthereumAction, WatchtowerEthereumActions};
+use crate::metrics::{Chain, Direction, WatchtowerMetrics};
+use crate::scheduler::{CheckSchedule, CheckScheduler};
+use crate::status::WatchtowerStatus;
 use crate::WatchtowerConfig;
 
 use anyhow::Result;
 use fuel_chain::FuelChain;
 use fungible_token_contract::FungibleTokenContract;
-use std::thread;
 use std::time::Duration;
 use tokio::task::JoinHandle;
 
@@ -13,156 +17,344 @@ pub mod fuel_chain;
 pub mod fungible_token_contract;
 
 pub static POLL_DURATION: Duration = Duration::from_millis(4000);
-pub static POLL_LOGGING_SKIP: u64 = 75;
+pub static POLL_LOGGING_SKIP: u32 = 75;
 pub static FUEL_CONNECTION_RETRIES: u64 = 2;
-pub static FUEL_BLOCK_TIME: u64 = 1;
+pub static FUEL_REQUEST_TIMEOUT: Duration = Duration::from_millis(10000);
+
+// clients and services shared by the fuel checks
+#[derive(Clone, Debug)]
+struct FuelWatcher {
+    fuel_chain: FuelChain,
+    fungible_token_contract: FungibleTokenContract,
+    alerts: WatchtowerAlerts,
+    actions: WatchtowerEthereumActions,
+    metrics: WatchtowerMetrics,
+}
+
+impl FuelWatcher {
+    fn for_check(&self, check: &str) -> Self {
+        let mut watcher = self.clone();
+        watcher.alerts = self.alerts.for_check(check);
+        watcher.actions = self.actions.for_check(check);
+        watcher
+    }
+}
 
 pub async fn start_fuel_watcher(
     config: &WatchtowerConfig,
+    clients: &WatchtowerClients,
+    status: &WatchtowerStatus,
+    metrics: &WatchtowerMetrics,
     actions: WatchtowerEthereumActions,
     alerts: WatchtowerAlerts,
 ) -> Result<JoinHandle<()>> {
-    let fuel_chain = FuelChain::new(config).await?;
-    let fungible_token_contract = FungibleTokenContract::new(config).await?;
-
+    let watcher = FuelWatcher {
+        fuel_chain: clients.fuel_chain.clone(),
+        fungible_token_contract: FungibleTokenContract::new(config).await?,
+        alerts: alerts.clone(),
+        actions: actions.clone(),
+        metrics: metrics.clone(),
+    };
     let watch_config = config.fuel_client_watcher.clone();
+    let mut scheduler = CheckScheduler::new("Fuel watcher", status.clone(), alerts, actions);
+
+    // update the log every so often to notify that everything is working
+    let status_watcher = watcher.clone();
+    scheduler.schedule(
+        String::from("fuel watcher status"),
+        CheckSchedule::new(None, None, POLL_DURATION * POLL_LOGGING_SKIP),
+        AlertLevel::None,
+        EthereumAction::None,
+        move || log_status(status_watcher.clone()),
+    );
+
+    // check chain connection
+    let connection_alert = watch_config.connection_alert.clone();
+    if connection_alert.alert_level != AlertLevel::None {
+        let name = String::from("fuel connection");
+        let check_watcher = watcher.for_check(&name);
+        let check_alert = connection_alert.clone();
+        scheduler.schedule(
+            name,
+            CheckSchedule::new(
+                connection_alert.check_interval,
+                connection_alert.check_timeout,
+                POLL_DURATION,
+            ),
+            connection_alert.alert_level,
+            connection_alert.alert_action,
+            move || check_connection(check_watcher.clone(), check_alert.clone()),
+        );
+    }
+
+    // check block production
+    let block_production_alert = watch_config.block_production_alert.clone();
+    if block_production_alert.alert_level != AlertLevel::None {
+        let name = String::from("fuel block production");
+        let check_watcher = watcher.for_check(&name);
+        let check_alert = block_production_alert.clone();
+        let connection_alert = watch_config.connection_alert.clone();
+        scheduler.schedule(
+            name,
+            CheckSchedule::new(
+                block_production_alert.check_interval,
+                block_production_alert.check_timeout,
+                POLL_DURATION,
+            ),
+            block_production_alert.alert_level,
+            block_production_alert.alert_action,
+            move || check_block_production(check_watcher.clone(), check_alert.clone(), connection_alert.clone()),
+        );
+    }
+
+    // check graphql endpoints are consistent with each other
+    let endpoint_consensus_alert = watch_config.endpoint_consensus_alert.clone();
+    if endpoint_consensus_alert.alert_level != AlertLevel::None {
+        let name = String::from("fuel endpoint consensus");
+        let check_watcher = watcher.for_check(&name);
+        let check_alert = endpoint_consensus_alert.clone();
+        scheduler.schedule(
+            name,
+            CheckSchedule::new(
+                endpoint_consensus_alert.check_interval,
+                endpoint_consensus_alert.check_timeout,
+                POLL_DURATION,
+            ),
+            endpoint_consensus_alert.alert_level,
+            endpoint_consensus_alert.alert_action,
+            move || check_endpoint_consensus(check_watcher.clone(), check_alert.clone()),
+        );
+    }
+
+    // check base asset withdrawals
+    for portal_withdraw_alert in watch_config.portal_withdraw_alerts {
+        if portal_withdraw_alert.alert_level != AlertLevel::None {
+            let name = format!(
+                "base asset withdrawals over {} seconds",
+                portal_withdraw_alert.time_frame
+            );
+            let check_watcher = watcher.for_check(&name);
+            let check_alert = portal_withdraw_alert.clone();
+            scheduler.schedule(
+                name,
+                CheckSchedule::new(
+                    portal_withdraw_alert.check_interval,
+                    portal_withdraw_alert.check_timeout,
+                    POLL_DURATION,
+                ),
+                portal_withdraw_alert.alert_level,
+                portal_withdraw_alert.alert_action,
+                move || check_portal_withdrawals(check_watcher.clone(), check_alert.clone()),
+            );
+        }
+    }
+
+    // check ERC20 token withdrawals
+    for gateway_withdraw_alert in watch_config.gateway_withdraw_alerts {
+        if gateway_withdraw_alert.alert_level != AlertLevel::None {
+            let name = format!(
+                "{} withdrawals over {} seconds",
+                gateway_withdraw_alert.token_name, gateway_withdraw_alert.time_frame
+            );
+            let check_watcher = watcher.for_check(&name);
+            let check_alert = gateway_withdraw_alert.clone();
+            scheduler.schedule(
+                name,
+                CheckSchedule::new(
+                    gateway_withdraw_alert.check_interval,
+                    gateway_withdraw_alert.check_timeout,
+                    POLL_DURATION,
+                ),
+                gateway_withdraw_alert.alert_level,
+                gateway_withdraw_alert.alert_action,
+                move || check_gateway_withdrawals(check_watcher.clone(), check_alert.clone()),
+            );
+        }
+    }
 
     // start thread
-    let handle = tokio::spawn(async move {
-        loop {
-            // update the log every so often to notify that everything is working
-            alerts.alert(String::from("Watching fuel chain."), AlertLevel::Info);
-            for _i in 0..POLL_LOGGING_SKIP {
-                // check chain connection
-                if watch_config.connection_alert.alert_level != AlertLevel::None {
-                    match fuel_chain.check_connection().await {
-                        Ok(_) => {}
-                        Err(e) => {
-                            alerts.alert(
-                                format!("Failed to check fuel connection: {e}"),
-                                watch_config.connection_alert.alert_level.clone(),
-                            );
-                            actions.action(
-                                watch_config.connection_alert.alert_action.clone(),
-                                Some(watch_config.connection_alert.alert_level.clone()),
-                            );
-                        }
-                    }
-                }
+    let handle = tokio::spawn(scheduler.run());
 
-                // check block production
-                if watch_config.block_production_alert.alert_level != AlertLevel::None {
-                    match fuel_chain.get_seconds_since_last_block().await {
-                        Ok(seconds_since_last_block) => {
-                            if seconds_since_last_block > watch_config.block_production_alert.max_block_time {
-                                alerts.alert(
-                                    format!(
-                                        "Next fuel block is taking longer than {} seconds. Last block was {} seconds ago.",
-                                        watch_config.block_production_alert.max_block_time, seconds_since_last_block
-                                    ),
-                                    watch_config.block_production_alert.alert_level.clone(),
-                                );
-                                actions.action(
-                                    watch_config.block_production_alert.alert_action.clone(),
-                                    Some(watch_config.block_production_alert.alert_level.clone()),
-                                );
-                            }
-                        }
-                        Err(e) => {
-                            alerts.alert(
-                                format!("Failed to check fuel block production: {e}"),
-                                watch_config.connection_alert.alert_level.clone(),
-                            );
-                            actions.action(
-                                watch_config.connection_alert.alert_action.clone(),
-                                Some(watch_config.connection_alert.alert_level.clone()),
-                            );
-                        }
-                    }
-                }
+    Ok(handle)
+}
 
-                // check base asset withdrawals
-                for portal_withdraw_alert in &watch_config.portal_withdraw_alerts {
-                    if portal_withdraw_alert.alert_level != AlertLevel::None {
-                        let time_frame = portal_withdraw_alert.time_frame;
-                        match fuel_chain.get_amount_withdrawn(time_frame).await {
-                            Ok(amount) => {
-                                let amount_threshold = FuelChain::get_value(portal_withdraw_alert.amount, 9);
-                                if amount >= amount_threshold {
-                                    alerts.alert(
-                                        format!(
-                                            "Base asset withdraw threshold of {} over {} seconds has been reached. Amount withdrawn: {}",
-                                            amount_threshold, time_frame, amount
-                                        ),
-                                        portal_withdraw_alert.alert_level.clone(),
-                                    );
-                                    actions.action(
-                                        portal_withdraw_alert.alert_action.clone(),
-                                        Some(portal_withdraw_alert.alert_level.clone()),
-                                    );
-                                }
-                            }
-                            Err(e) => {
-                                alerts.alert(
-                                    format!("Failed to check base asset withdrawals: {e}"),
-                                    portal_withdraw_alert.alert_level.clone(),
-                                );
-                                actions.action(
-                                    portal_withdraw_alert.alert_action.clone(),
-                                    Some(portal_withdraw_alert.alert_level.clone()),
-                                );
-                            }
-                        }
-                    }
-                }
+async fn log_status(watcher: FuelWatcher) -> Result<()> {
+    watcher
+        .alerts
+        .alert(String::from("Watching fuel chain."), AlertLevel::Info);
+    Ok(())
+}
 
-                // check ERC20 token withdrawals
-                for gateway_withdraw_alert in &watch_config.gateway_withdraw_alerts {
-                    if gateway_withdraw_alert.alert_level != AlertLevel::None {
-                        match fungible_token_contract
-                            .get_amount_withdrawn(
-                                gateway_withdraw_alert.time_frame,
-                                &gateway_withdraw_alert.token_address,
-                            )
-                            .await
-                        {
-                            Ok(amount) => {
-                                let amount_threshold = FuelChain::get_value(
-                                    gateway_withdraw_alert.amount,
-                                    gateway_withdraw_alert.token_decimals,
-                                );
-                                if amount >= amount_threshold {
-                                    alerts.alert(
-                                        format!(
-                                            "ERC20 withdraw threshold of {}{} over {} seconds has been reached. Amount withdrawn: {}{}",
-                                            amount_threshold, gateway_withdraw_alert.token_name, gateway_withdraw_alert.time_frame, amount, gateway_withdraw_alert.token_name
-                                        ),
-                                        gateway_withdraw_alert.alert_level.clone(),
-                                    );
-                                    actions.action(
-                                        gateway_withdraw_alert.alert_action.clone(),
-                                        Some(gateway_withdraw_alert.alert_level.clone()),
-                                    );
-                                }
-                            }
-                            Err(e) => {
-                                alerts.alert(
-                                    format!("Failed to check ERC20 withdrawals: {e}"),
-                                    gateway_withdraw_alert.alert_level.clone(),
-                                );
-                                actions.action(
-                                    gateway_withdraw_alert.alert_action.clone(),
-                                    Some(gateway_withdraw_alert.alert_level.clone()),
-                                );
-                            }
-                        }
-                    }
-                }
+async fn check_connection(watcher: FuelWatcher, connection_alert: GenericAlert) -> Result<()> {
+    match watcher.fuel_chain.check_connection().await {
+        Ok(_) => {}
+        Err(e) => {
+            watcher.alerts.alert(
+                format!("Failed to check fuel connection: {e}"),
+                connection_alert.alert_level.clone(),
+            );
+            watcher.actions.action(
+                connection_alert.alert_action.clone(),
+                Some(connection_alert.alert_level.clone()),
+            );
+            return Err(e);
+        }
+    }
+    Ok(())
+}
 
-                thread::sleep(POLL_DURATION);
+async fn check_block_production(
+    watcher: FuelWatcher,
+    block_production_alert: BlockProductionAlert,
+    connection_alert: GenericAlert,
+) -> Result<()> {
+    match watcher.fuel_chain.get_seconds_since_last_block().await {
+        Ok(seconds_since_last_block) => {
+            watcher
+                .metrics
+                .set_seconds_since_last_block(Chain::Fuel, seconds_since_last_block);
+            if seconds_since_last_block > block_production_alert.max_block_time {
+                watcher.alerts.alert(
+                    format!(
+                        "Next fuel block is taking longer than {} seconds. Last block was {} seconds ago.",
+                        block_production_alert.max_block_time, seconds_since_last_block
+                    ),
+                    block_production_alert.alert_level.clone(),
+                );
+                watcher.actions.action(
+                    block_production_alert.alert_action.clone(),
+                    Some(block_production_alert.alert_level.clone()),
+                );
             }
         }
-    });
+        Err(e) => {
+            watcher.alerts.alert(
+                format!("Failed to check fuel block production: {e}"),
+                connection_alert.alert_level.clone(),
+            );
+            watcher.actions.action(
+                connection_alert.alert_action.clone(),
+                Some(connection_alert.alert_level.clone()),
+            );
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+async fn check_endpoint_consensus(watcher: FuelWatcher, endpoint_consensus_alert: GenericAlert) -> Result<()> {
+    match watcher.fuel_chain.check_endpoint_consensus().await {
+        Ok(_) => {}
+        Err(e) => {
+            watcher.alerts.alert(
+                format!("Fuel graphql endpoints are inconsistent: {e}"),
+                endpoint_consensus_alert.alert_level.clone(),
+            );
+            watcher.actions.action(
+                endpoint_consensus_alert.alert_action.clone(),
+                Some(endpoint_consensus_alert.alert_level.clone()),
+            );
+            return Err(e);
+        }
+    }
+    Ok(())
+}
 
-    Ok(handle)
+async fn check_portal_withdrawals(watcher: FuelWatcher, portal_withdraw_alert: WithdrawAlert) -> Result<()> {
+    let time_frame = portal_withdraw_alert.time_frame;
+    match watcher.fuel_chain.get_amount_withdrawn(time_frame).await {
+        Ok(amount) => {
+            watcher
+                .metrics
+                .set_window_total(Direction::Withdraw, "ETH", time_frame, FuelChain::get_units(amount, 9));
+            let amount_threshold = FuelChain::get_value(portal_withdraw_alert.amount, 9);
+            if amount >= amount_threshold {
+                watcher.alerts.alert(
+                    format!(
+                        "Base asset withdraw threshold of {} over {} seconds has been reached. Amount withdrawn: {}",
+                        amount_threshold, time_frame, amount
+                    ),
+                    portal_withdraw_alert.alert_level.clone(),
+                );
+                watcher.actions.action(
+                    portal_withdraw_alert.alert_action.clone(),
+                    Some(portal_withdraw_alert.alert_level.clone()),
+                );
+            }
+        }
+        Err(e) => {
+            watcher.alerts.alert(
+                format!("Failed to check base asset withdrawals: {e}"),
+                portal_withdraw_alert.alert_level.clone(),
+            );
+            watcher.actions.action(
+                portal_withdraw_alert.alert_action.clone(),
+                Some(portal_withdraw_alert.alert_level.clone()),
+            );
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+async fn check_gateway_withdrawals(watcher: FuelWatcher, gateway_withdraw_alert: WithdrawAlert) -> Result<()> {
+    let amount_result = match watcher.fuel_chain.get_latest_block_height().await {
+        Ok(latest_height) => {
+            match watcher
+                .fuel_chain
+                .get_block_height_from_time_frame(gateway_withdraw_alert.time_frame, latest_height)
+                .await
+            {
+                Ok(start_height) => {
+                    watcher
+                        .fungible_token_contract
+                        .get_amount_withdrawn(start_height, &gateway_withdraw_alert.token_address)
+                        .await
+                }
+                Err(e) => Err(e),
+            }
+        }
+        Err(e) => Err(e),
+    };
+    match amount_result {
+        Ok(amount) => {
+            watcher.metrics.set_window_total(
+                Direction::Withdraw,
+                &gateway_withdraw_alert.token_name,
+                gateway_withdraw_alert.time_frame,
+                FuelChain::get_units(amount, gateway_withdraw_alert.token_decimals),
+            );
+            let amount_threshold =
+                FuelChain::get_value(gateway_withdraw_alert.amount, gateway_withdraw_alert.token_decimals);
+            if amount >= amount_threshold {
+                watcher.alerts.alert(
+                    format!(
+                        "ERC20 withdraw threshold of {}{} over {} seconds has been reached. Amount withdrawn: {}{}",
+                        amount_threshold,
+                        gateway_withdraw_alert.token_name,
+                        gateway_withdraw_alert.time_frame,
+                        amount,
+                        gateway_withdraw_alert.token_name
+                    ),
+                    gateway_withdraw_alert.alert_level.clone(),
+                );
+                watcher.actions.action(
+                    gateway_withdraw_alert.alert_action.clone(),
+                    Some(gateway_withdraw_alert.alert_level.clone()),
+                );
+            }
+        }
+        Err(e) => {
+            watcher.alerts.alert(
+                format!("Failed to check ERC20 withdrawals: {e}"),
+                gateway_withdraw_alert.alert_level.clone(),
+            );
+            watcher.actions.action(
+                gateway_withdraw_alert.alert_action.clone(),
+                Some(gateway_withdraw_alert.alert_level.clone()),
+            );
+            return Err(e);
+        }
+    }
+    Ok(())
 }
@@ -1,155 +1,355 @@
-use super::{FUEL_BLOCK_TIME, FUEL_CONNECTION_RETRIES};
+use super::{FUEL_CONNECTION_RETRIES, FUEL_REQUEST_TIMEOUT};
+use crate::block_timestamps::BlockTimestamps;
 use crate::WatchtowerConfig;
 
 use anyhow::Result;
 use fuels::{
     client::{PageDirection, PaginationRequest},
     prelude::Provider,
+    types::block::Block,
 };
+use futures::future::join_all;
+use std::cmp::min;
+use std::fmt::Display;
+use std::future::Future;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Clone, Debug)]
 pub struct FuelChain {
+    endpoints: Vec<FuelEndpoint>,
+    quorum: usize,
+    block_timestamps: BlockTimestamps,
+    log_tag: String,
+}
+
+#[derive(Clone, Debug)]
+struct FuelEndpoint {
+    url: String,
     provider: Provider,
 }
 
 impl FuelChain {
     pub async fn new(config: &WatchtowerConfig) -> Result<Self> {
-        // setup provider and check that it is valid
-        let provider = Provider::connect(&config.fuel_graphql).await?;
-        let provider_result = provider.chain_info().await;
-        match provider_result {
-            Err(e) => Err(anyhow::anyhow!("Invalid fuel graphql endpoint: {e}")),
-            Ok(_) => Ok(FuelChain { provider }),
+        let quorum = config.fuel_graphql_quorum;
+        if quorum == 0 || quorum > config.fuel_graphql.len() {
+            return Err(anyhow::anyhow!(
+                "Invalid fuel graphql quorum of {} for {} endpoints.",
+                quorum,
+                config.fuel_graphql.len()
+            ));
         }
+
+        // setup providers and check that enough of them are valid
+        let log_tag = config.log_tag();
+        let mut endpoints = Vec::new();
+        let mut valid_endpoints = 0;
+        for url in &config.fuel_graphql {
+            let provider = Provider::connect(url).await?;
+            match provider.chain_info().await {
+                Ok(_) => valid_endpoints += 1,
+                Err(e) => log::warn!("{log_tag}Invalid fuel graphql endpoint {url}: {e}"),
+            }
+            endpoints.push(FuelEndpoint {
+                url: url.clone(),
+                provider,
+            });
+        }
+        if valid_endpoints < quorum {
+            return Err(anyhow::anyhow!(
+                "Only {} of the {} required fuel graphql endpoints are valid.",
+                valid_endpoints,
+                quorum
+            ));
+        }
+
+        Ok(FuelChain {
+            endpoints,
+            quorum,
+            block_timestamps: BlockTimestamps::new(),
+            log_tag,
+        })
     }
 
     pub async fn check_connection(&self) -> Result<()> {
-        for i in 0..FUEL_CONNECTION_RETRIES {
-            match self.provider.chain_info().await {
-                Ok(_) => return Ok(()),
-                Err(e) => {
-                    if i == FUEL_CONNECTION_RETRIES - 1 {
-                        return Err(anyhow::anyhow!("{e}"));
-                    }
+        self.request(|provider| async move { provider.chain_info().await })
+            .await?;
+        Ok(())
+    }
+
+    // checks that endpoints report the same block hash at the highest height they have all reached
+    pub async fn check_endpoint_consensus(&self) -> Result<()> {
+        let heights = self
+            .request_all(|provider| async move { provider.latest_block_height().await })
+            .await;
+        let mut issues = Vec::new();
+        let mut common_height = u64::MAX;
+        let mut responded = 0;
+        for (url, height) in &heights {
+            match height {
+                Ok(height) => {
+                    common_height = min(common_height, *height);
+                    responded += 1;
+                }
+                Err(e) => issues.push(format!("{url} is down ({e})")),
+            }
+        }
+        if responded < self.quorum {
+            issues.push(format!(
+                "only {} of the {} required endpoints responded",
+                responded, self.quorum
+            ));
+            return Err(anyhow::anyhow!("{}", issues.join("; ")));
+        }
+
+        let blocks = self
+            .request_all(|provider| async move { provider.client.block_by_height(common_height).await })
+            .await;
+        let mut block_ids: Vec<(String, Vec<String>)> = Vec::new();
+        for (url, block) in blocks {
+            if let Ok(Some(block)) = block {
+                let block_id = Block::from(block).id.to_string();
+                match block_ids.iter_mut().find(|(id, _)| *id == block_id) {
+                    Some((_, urls)) => urls.push(url),
+                    None => block_ids.push((block_id, vec![url])),
                 }
             }
         }
+        if block_ids.len() > 1 {
+            let groups: Vec<String> = block_ids
+                .iter()
+                .map(|(id, urls)| format!("{} ({})", id, urls.join(", ")))
+                .collect();
+            issues.push(format!(
+                "endpoints report different blocks at height {}: {}",
+                common_height,
+                groups.join(" vs ")
+            ));
+        }
+
+        if !issues.is_empty() {
+            return Err(anyhow::anyhow!("{}", issues.join("; ")));
+        }
         Ok(())
     }
 
+    // returns the time since the last block that at least a quorum of endpoints have seen
     pub async fn get_seconds_since_last_block(&self) -> Result<u32> {
-        for i in 0..FUEL_CONNECTION_RETRIES {
-            match self.provider.chain_info().await {
-                Ok(info) => {
-                    return match info.latest_block.header.time {
-                        Some(time) => {
-                            let last_block_timestamp = (time.timestamp_millis() as u64) / 1000;
-                            let millis_now =
-                                (SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64) / 1000;
-                            if millis_now >= last_block_timestamp {
-                                Ok((millis_now - last_block_timestamp) as u32)
-                            } else {
-                                Err(anyhow::anyhow!("Block time is ahead of current time"))
-                            }
-                        }
-                        None => Err(anyhow::anyhow!("Failed to get latest block")),
-                    }
-                }
-                Err(e) => {
-                    if i == FUEL_CONNECTION_RETRIES - 1 {
-                        return Err(anyhow::anyhow!("{e}"));
-                    }
-                }
+        let chain_infos = self
+            .request_all(|provider| async move { provider.chain_info().await })
+            .await;
+        let mut last_block_timestamps = Vec::new();
+        for (url, chain_info) in chain_infos {
+            match chain_info {
+                Ok(info) => match info.latest_block.header.time {
+                    Some(time) => last_block_timestamps.push((time.timestamp_millis() as u64) / 1000),
+                    None => log::warn!("{}Fuel graphql endpoint {url} failed to get latest block", self.log_tag),
+                },
+                Err(e) => log::warn!("{}Fuel graphql endpoint {url} failed: {e}", self.log_tag),
+            }
+        }
+        if last_block_timestamps.len() < self.quorum {
+            return Err(anyhow::anyhow!("Failed to get latest block"));
+        }
+        last_block_timestamps.sort_by(|a, b| b.cmp(a));
+        let last_block_timestamp = last_block_timestamps[self.quorum - 1];
+
+        let millis_now = (SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64) / 1000;
+        if millis_now >= last_block_timestamp {
+            Ok((millis_now - last_block_timestamp) as u32)
+        } else {
+            Err(anyhow::anyhow!("Block time is ahead of current time"))
+        }
+    }
+
+    pub async fn get_latest_block_height(&self) -> Result<u64> {
+        self.request(|provider| async move { provider.latest_block_height().await })
+            .await
+    }
+
+    pub async fn get_block_timestamp(&self, height: u64) -> Result<u64> {
+        if let Some(timestamp) = self.block_timestamps.get(height) {
+            return Ok(timestamp);
+        }
+        let block_result = self
+            .request(|provider| async move { provider.client.block_by_height(height).await })
+            .await?;
+        match block_result.and_then(|block| Block::from(block).header.time) {
+            Some(time) => {
+                let timestamp = (time.timestamp_millis() as u64) / 1000;
+                self.block_timestamps.insert(height, timestamp);
+                Ok(timestamp)
             }
+            None => Err(anyhow::anyhow!("Failed to get block {height}")),
         }
-        Ok(0)
+    }
+
+    // finds the first block produced within the last `time_frame` seconds
+    pub async fn get_block_height_from_time_frame(&self, time_frame: u32, latest_height: u64) -> Result<u64> {
+        // fuel block production is not fixed so the first search covers the whole chain
+        self.block_timestamps
+            .find_block_from_time_frame(time_frame, 0, latest_height, |height| self.get_block_timestamp(height))
+            .await
     }
 
     pub async fn get_amount_withdrawn(&self, timeframe: u32) -> Result<u64> {
-        let num_blocks = match usize::try_from(timeframe as u64 / FUEL_BLOCK_TIME) {
+        let latest_height = self.get_latest_block_height().await?;
+        let start_height = self.get_block_height_from_time_frame(timeframe, latest_height).await?;
+        if start_height > latest_height {
+            return Ok(0);
+        }
+        let num_blocks = match usize::try_from(latest_height - start_height + 1) {
             Ok(val) => val,
             Err(e) => return Err(anyhow::anyhow!("{e}")),
         };
-        for i in 0..FUEL_CONNECTION_RETRIES {
-            let req = PaginationRequest {
-                cursor: None,
-                results: num_blocks,
-                direction: PageDirection::Backward,
-            };
-            match self.provider.get_blocks(req).await {
-                Ok(blocks_result) => {
-                    let mut total: u64 = 0;
-                    for block in blocks_result.results {
-                        for tx_id in block.transactions {
-                            match self.get_amount_withdrawn_from_tx(&tx_id.to_string()).await {
-                                Ok(amount) => {
-                                    total += amount;
-                                }
-                                Err(e) => return Err(anyhow::anyhow!("{e}")),
-                            }
-                        }
-                    }
-                    return Ok(total);
-                }
-                Err(e) => {
-                    if i == FUEL_CONNECTION_RETRIES - 1 {
-                        return Err(anyhow::anyhow!("{e}"));
-                    }
-                }
+        let blocks_result = self
+            .request(|provider| async move {
+                let req = PaginationRequest {
+                    cursor: None,
+                    results: num_blocks,
+                    direction: PageDirection::Backward,
+                };
+                provider.get_blocks(req).await
+            })
+            .await?;
+        let mut total: u64 = 0;
+        for block in blocks_result.results {
+            for tx_id in block.transactions {
+                total += self.get_amount_withdrawn_from_tx(&tx_id.to_string()).await?;
             }
         }
-        Ok(0)
+        Ok(total)
     }
 
     pub async fn get_amount_withdrawn_from_tx(&self, tx_id: &str) -> Result<u64> {
-        for i in 0..FUEL_CONNECTION_RETRIES {
-            match self.provider.get_transaction_by_id(&tx_id).await {
-                Ok(tx_result) => {
-                    match tx_result {
-                        Some(tx) => {
-                            // TODO
-
-                            return Ok(0); ///////////////////////////////
-                        }
-                        None => {
-                            if i == FUEL_CONNECTION_RETRIES - 1 {
-                                return Err(anyhow::anyhow!("Failed to find details for transaction: {tx_id}"));
-                            }
-                        }
-                    }
-                }
-                Err(e) => {
-                    if i == FUEL_CONNECTION_RETRIES - 1 {
-                        return Err(anyhow::anyhow!("{e}"));
-                    }
-                }
+        let tx_result = self
+            .request(|provider| async move { provider.get_transaction_by_id(tx_id).await })
+            .await?;
+        match tx_result {
+            Some(tx) => {
+                // TODO
+
+                Ok(0) ///////////////////////////////
             }
+            None => Err(anyhow::anyhow!("Failed to find details for transaction: {tx_id}")),
         }
-
-        Ok(0)
     }
 
+    // checks the block exists on at least a quorum of endpoints, and that the endpoints agree on it
     pub async fn verify_block_commit(&self, block_hash: &str) -> Result<bool> {
-        for i in 0..FUEL_CONNECTION_RETRIES {
-            match self.provider.block(block_hash).await {
-                Ok(_) => return Ok(true),
-                Err(e) => {
-                    if i == FUEL_CONNECTION_RETRIES - 1 {
-                        return Err(anyhow::anyhow!("{e}"));
+        let blocks = self
+            .request_all(|provider| async move { provider.block(block_hash).await })
+            .await;
+        let mut found = Vec::new();
+        let mut missing = Vec::new();
+        for (url, block) in &blocks {
+            match block {
+                Ok(Some(_)) => found.push(url.as_str()),
+                Ok(None) => missing.push(url.as_str()),
+                Err(e) => log::warn!("{}Fuel graphql endpoint {url} failed: {e}", self.log_tag),
+            }
+        }
+        if found.len() + missing.len() < self.quorum {
+            return Err(anyhow::anyhow!(
+                "Only {} of the {} required fuel graphql endpoints responded",
+                found.len() + missing.len(),
+                self.quorum
+            ));
+        }
+        if !found.is_empty() && !missing.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Fuel graphql endpoints disagree on block {}: found by [{}], missing from [{}]",
+                block_hash,
+                found.join(", "),
+                missing.join(", ")
+            ));
+        }
+        Ok(!found.is_empty())
+    }
+
+    // runs the request against each endpoint in order (with retries) until one succeeds
+    async fn request<T, E, F, Fut>(&self, request: F) -> Result<T>
+    where
+        E: Display,
+        F: Fn(Provider) -> Fut,
+        Fut: Future<Output = std::result::Result<T, E>>,
+    {
+        let mut last_error = anyhow::anyhow!("No fuel graphql endpoints configured.");
+        for endpoint in &self.endpoints {
+            for _i in 0..FUEL_CONNECTION_RETRIES {
+                match FuelChain::timeout(request(endpoint.provider.clone())).await {
+                    Ok(result) => return Ok(result),
+                    Err(e) => {
+                        log::warn!("{}Fuel graphql endpoint {} failed: {}", self.log_tag, endpoint.url, e);
+                        last_error = e;
                     }
                 }
             }
         }
-        Ok(true)
+        Err(last_error)
+    }
+
+    // runs the request against all endpoints at once
+    async fn request_all<T, E, F, Fut>(&self, request: F) -> Vec<(String, Result<T>)>
+    where
+        E: Display,
+        F: Fn(Provider) -> Fut,
+        Fut: Future<Output = std::result::Result<T, E>>,
+    {
+        let requests = self.endpoints.iter().map(|endpoint| {
+            let response = FuelChain::timeout(request(endpoint.provider.clone()));
+            async move { (endpoint.url.clone(), response.await) }
+        });
+        join_all(requests).await
+    }
+
+    // limits how long a single request can hold up the checks waiting on it
+    async fn timeout<T, E, Fut>(request: Fut) -> Result<T>
+    where
+        E: Display,
+        Fut: Future<Output = std::result::Result<T, E>>,
+    {
+        match tokio::time::timeout(FUEL_REQUEST_TIMEOUT, request).await {
+            Ok(Ok(result)) => Ok(result),
+            Ok(Err(e)) => Err(anyhow::anyhow!("{e}")),
+            Err(_) => Err(anyhow::anyhow!(
+                "Request timed out after {} seconds",
+                FUEL_REQUEST_TIMEOUT.as_secs()
+            )),
+        }
     }
 
     pub fn get_value(value_fp: f64, decimals: u8) -> u64 {
         let decimals_p1 = if decimals < 9 { decimals } else { decimals - 9 };
         let decimals_p2 = decimals - decimals_p1;
 
-        let value = value_fp * (10.0 as f64).powf(decimals_p1 as f64);
-        let value = (value as u64) * (10 as u64).pow(decimals_p2 as u32);
-        value
+        let value = value_fp * 10f64.powf(decimals_p1 as f64);
+        (value as u64) * 10u64.pow(decimals_p2 as u32)
+    }
+
+    // inverse of get_value, for reporting amounts in whole tokens
+    pub fn get_units(value: u64, decimals: u8) -> f64 {
+        value as f64 / 10f64.powi(decimals as i32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_config;
+    use fuels::test_helpers::setup_test_provider;
+
+    #[tokio::test]
+    async fn only_known_blocks_verify() {
+        let (provider, address) = setup_test_provider(vec![], vec![], None, None).await;
+        let block_hash = format!("{:#x}", provider.chain_info().await.unwrap().latest_block.id);
+
+        let mut config = test_config();
+        config.fuel_graphql = vec![format!("http://{address}")];
+        config.fuel_graphql_quorum = 1;
+        let fuel_chain = FuelChain::new(&config).await.unwrap();
+
+        assert!(fuel_chain.verify_block_commit(&block_hash).await.unwrap());
+        let unknown_hash = format!("0x{}", "11".repeat(32));
+        assert!(!fuel_chain.verify_block_commit(&unknown_hash).await.unwrap());
     }
 }
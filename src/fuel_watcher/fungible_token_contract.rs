@@ -1,4 +1,4 @@
-use super::{FUEL_BLOCK_TIME, FUEL_CONNECTION_RETRIES};
+use super::FUEL_CONNECTION_RETRIES;
 use crate::WatchtowerConfig;
 
 use anyhow::Result;
@@ -12,8 +12,7 @@ impl FungibleTokenContract {
         Ok(FungibleTokenContract {})
     }
 
-    pub async fn get_amount_withdrawn(&self, timeframe: u32, token_address: &str) -> Result<u64> {
-        let block_offset = timeframe as u64 / FUEL_BLOCK_TIME;
+    pub async fn get_amount_withdrawn(&self, from_height: u64, token_address: &str) -> Result<u64> {
         // TODO
 
         Ok(0)
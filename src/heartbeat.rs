@@ -0,0 +1,147 @@
+use crate::config::HeartbeatConfig;
+use crate::status::WatchtowerStatus;
+
+use anyhow::Result;
+use reqwest::{Client, Url};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+pub static HEARTBEAT_TIMEOUT: Duration = Duration::from_millis(10000);
+
+// starts a thread that pings the heartbeat url, but only while every watcher is running and every one of their checks
+// has succeeded recently, so an external monitor notices when the watchtower is down, stuck or crash looping
+pub fn start_heartbeat(config: &HeartbeatConfig, status: WatchtowerStatus, log_tag: String) -> Result<JoinHandle<()>> {
+    let client = Client::builder().timeout(HEARTBEAT_TIMEOUT).build()?;
+    let url = Url::parse(&config.url)?;
+    let interval = Duration::from_secs(config.interval);
+
+    let handle = tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+
+            let problems = get_problems(&status);
+            if !problems.is_empty() {
+                log::warn!("{}Holding back heartbeat: {}", log_tag, problems.join(", "));
+                continue;
+            }
+            let response = client.get(url.clone()).send().await;
+            if let Err(e) = response.and_then(|response| response.error_for_status()) {
+                log::warn!("{}Failed to send heartbeat: {}", log_tag, e);
+            }
+        }
+    });
+
+    Ok(handle)
+}
+
+// what keeps the watchtower from being healthy: a watcher not running (including one waiting to be restarted or never
+// started), a running watcher without any checks, or a check that has not succeeded within its max age
+fn get_problems(status: &WatchtowerStatus) -> Vec<String> {
+    let watchers = status.get_watchers();
+    let checks = status.get_checks();
+    if watchers.is_empty() {
+        return vec![String::from("no watcher has started")];
+    }
+
+    let mut problems = vec![];
+    for (watcher, running) in watchers {
+        if !running {
+            problems.push(format!("{watcher} is not running"));
+        } else if !checks.iter().any(|check| check.watcher == watcher) {
+            problems.push(format!("{watcher} has no checks"));
+        }
+    }
+    for check in checks.iter().filter(|check| !check.succeeded_recently()) {
+        problems.push(format!(
+            "{} ({}) has not succeeded recently",
+            check.check, check.watcher
+        ));
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::MockServer;
+    use serde_json::json;
+
+    // a running watcher whose only check has just succeeded
+    fn healthy_status() -> WatchtowerStatus {
+        let status = WatchtowerStatus::new();
+        status.set_watcher_running("Fuel watcher", true);
+        status.register_check("Fuel watcher", "Block production", Duration::from_secs(60));
+        status.record_success("Fuel watcher", "Block production");
+        status
+    }
+
+    #[test]
+    fn healthy_watchers_have_no_problems() {
+        assert!(get_problems(&healthy_status()).is_empty());
+    }
+
+    #[test]
+    fn watchers_never_started_are_problems() {
+        assert_eq!(get_problems(&WatchtowerStatus::new()), vec!["no watcher has started"]);
+
+        // the supervisor lists watchers as not running until they have started
+        let status = healthy_status();
+        status.set_watcher_running("Ethereum watcher", false);
+        assert_eq!(get_problems(&status), vec!["Ethereum watcher is not running"]);
+
+        // and a started watcher has to schedule its checks
+        status.set_watcher_running("Ethereum watcher", true);
+        assert_eq!(get_problems(&status), vec!["Ethereum watcher has no checks"]);
+    }
+
+    #[test]
+    fn crash_looping_watchers_are_problems() {
+        let status = healthy_status();
+        status.register_check("Fuel watcher", "Withdrawals", Duration::ZERO);
+        status.record_success("Fuel watcher", "Withdrawals");
+        std::thread::sleep(Duration::from_millis(10));
+
+        // restarted, with its checks registered again but not run yet
+        status.set_watcher_running("Fuel watcher", false);
+        assert_eq!(
+            get_problems(&status),
+            vec![
+                "Fuel watcher is not running",
+                "Withdrawals (Fuel watcher) has not succeeded recently"
+            ]
+        );
+        status.set_watcher_running("Fuel watcher", true);
+        status.register_check("Fuel watcher", "Block production", Duration::from_secs(60));
+        status.register_check("Fuel watcher", "Withdrawals", Duration::ZERO);
+        assert_eq!(
+            get_problems(&status),
+            vec!["Withdrawals (Fuel watcher) has not succeeded recently"]
+        );
+
+        // checks that never succeeded are problems even before they are overdue
+        status.register_check("Fuel watcher", "Deposits", Duration::from_secs(60));
+        assert_eq!(get_problems(&status).len(), 2);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn heartbeat_is_held_back_while_unhealthy() {
+        let monitor = MockServer::start(|_| (200, json!({})));
+        let status = WatchtowerStatus::new();
+        status.set_watcher_running("Fuel watcher", false);
+        let config = HeartbeatConfig {
+            url: monitor.url.clone(),
+            interval: 1,
+        };
+        let handle = start_heartbeat(&config, status.clone(), String::new()).unwrap();
+
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert!(monitor.requests().is_empty());
+
+        status.set_watcher_running("Fuel watcher", true);
+        status.register_check("Fuel watcher", "Block production", Duration::from_secs(60));
+        status.record_success("Fuel watcher", "Block production");
+        tokio::time::sleep(Duration::from_millis(1000)).await;
+        assert_eq!(monitor.requests().len(), 1);
+        handle.abort();
+    }
+}
@@ -1,73 +1,217 @@
+mod action_log;
+mod admin;
 mod alerts;
+mod block_timestamps;
+mod clients;
+mod commands;
 mod config;
+mod config_watcher;
 mod ethereum_actions;
 mod ethereum_watcher;
 mod fuel_watcher;
+mod heartbeat;
+mod metrics;
+mod network;
+mod scheduler;
+mod server;
+mod state;
+mod status;
+mod supervisor;
+#[cfg(test)]
+mod test_utils;
 
-pub use config::{load_config, WatchtowerConfig};
+pub use commands::{backfill, pause, print_actions, print_status, simulate};
+pub use config::{load_config, validate_config, validate_config_live, WatchtowerConfig};
+pub use ethereum_actions::EthereumAction;
 
-use alerts::{AlertLevel, WatchtowerAlerts};
+use config_watcher::start_config_watcher;
+
+use admin::{ReloadRequest, WatchtowerAdmin};
+use alerts::AlertLevel;
 use anyhow::Result;
-use ethereum_actions::WatchtowerEthereumActions;
-use ethereum_watcher::start_ethereum_watcher;
-use fuel_watcher::start_fuel_watcher;
+use metrics::WatchtowerMetrics;
+use network::{NetworkStopped, WatchtowerNetwork};
+use server::WatchtowerServer;
+use tokio::sync::{mpsc, watch};
 
-pub async fn run(config: &WatchtowerConfig) -> Result<()> {
-    // build alerts service
-    let alerts_result = WatchtowerAlerts::new(config);
-    if alerts_result.is_err() {
-        return Err(anyhow::anyhow!(
-            "Failed to setup alerts: {}",
-            alerts_result.err().unwrap()
-        ));
+// runs the watchtower for every network until it is asked to stop. With dry_run, actions are alerted on but never
+// sent
+pub async fn run(configs: &[WatchtowerConfig], config_files: &[String], dry_run: bool) -> Result<()> {
+    if dry_run {
+        log::warn!("Running in dry run mode. Contracts will not be paused.");
     }
-    let alerts = alerts_result.unwrap();
 
-    // build ethereum actions service
-    let actions_result = WatchtowerEthereumActions::new(config, alerts.clone()).await;
-    if actions_result.is_err() {
-        return Err(anyhow::anyhow!(
-            "Failed to setup actions: {}",
-            actions_result.err().unwrap()
-        ));
+    // start every network, each with its own clients, progress, alerts and actions
+    let metrics = WatchtowerMetrics::new();
+    let (shutdown_sender, shutdown) = watch::channel(false);
+    let (stopped_sender, mut stopped) = mpsc::unbounded_channel::<NetworkStopped>();
+    let mut networks = Vec::new();
+    let mut network_threads = Vec::new();
+    for config in configs {
+        let started =
+            WatchtowerNetwork::start(config, &metrics, shutdown.clone(), stopped_sender.clone(), dry_run).await;
+        // accounts can only be compared across networks once the chain of each is known
+        let started = started.and_then(|(network, thread)| {
+            let accounts_checked = network.check_accounts_not_shared(&networks);
+            networks.push(network);
+            network_threads.push(thread);
+            accounts_checked
+        });
+        if let Err(e) = started {
+            // stop the networks already started before giving up
+            let _ = shutdown_sender.send(true);
+            for thread in network_threads {
+                let _ = thread.await;
+            }
+            for network in &networks {
+                network.shutdown().await;
+            }
+            return Err(e);
+        }
     }
-    let actions = actions_result.unwrap();
 
-    // start fuel watcher
-    let fuel_watcher_result = start_fuel_watcher(config, actions.clone(), alerts.clone()).await;
-    if fuel_watcher_result.is_err() {
-        return Err(anyhow::anyhow!(
-            "Failed to start fuel watcher: {}",
-            fuel_watcher_result.err().unwrap()
-        ));
+    // start health, metrics and admin endpoints (shared by all networks, load_config rejects networks that set up
+    // the server differently)
+    let (reload_sender, mut reload_requests) = mpsc::unbounded_channel::<ReloadRequest>();
+    let server_thread = match &configs[0].http_server {
+        Some(server_config) => {
+            let server = WatchtowerServer::new(networks.clone(), metrics.clone());
+            let server = match &server_config.admin_token {
+                Some(admin_token) => server.with_admin(WatchtowerAdmin::new(
+                    admin_token.clone(),
+                    networks.clone(),
+                    reload_sender.clone(),
+                )),
+                None => {
+                    log::info!("No admin token set. The admin api is disabled.");
+                    server
+                }
+            };
+            match server.start(server_config) {
+                Ok(handle) => Some(handle),
+                Err(e) => return Err(anyhow::anyhow!("Failed to start http server: {}", e)),
+            }
+        }
+        None => None,
+    };
+
+    // reload the config whenever the files change or on SIGHUP
+    let config_watcher_thread = start_config_watcher(config_files, reload_sender.clone());
+
+    // wait for a shutdown signal, or for a network whose watchers could not be kept running, reloading the config on
+    // request
+    let shutdown_signal = shutdown_signal();
+    tokio::pin!(shutdown_signal);
+    let result = loop {
+        tokio::select! {
+            Some((network, result)) = stopped.recv() => {
+                break result.map_err(|e| anyhow::anyhow!("Network {}: {}", network, e));
+            }
+            signal = &mut shutdown_signal => {
+                match signal {
+                    Ok(signal) => log::info!("Received {}.", signal),
+                    Err(e) => log::error!("Failed to listen for shutdown signals: {}", e),
+                }
+                break Ok(());
+            }
+            Some(reloaded) = reload_requests.recv() => {
+                let result = reload_config(config_files, &networks);
+                match &result {
+                    Ok(changes) if changes.is_empty() => log::info!("Config unchanged, nothing to reload."),
+                    Ok(_) => {}
+                    Err(e) => {
+                        for network in &networks {
+                            network.alerts.alert(
+                                format!("Rejected config reload, the current config stays active: {e}"),
+                                AlertLevel::Warn,
+                            );
+                        }
+                    }
+                }
+                let _ = reloaded.send(result);
+            }
+        }
+    };
+
+    // shut down in order: stop the checks, let in-flight actions finish, save progress and push out the last alerts
+    for network in &networks {
+        network.alerts.alert(
+            String::from("Watchtower shutting down. The fuel and ethereum chains are no longer being watched."),
+            AlertLevel::Error,
+        );
+    }
+    if let Some(server_thread) = server_thread {
+        server_thread.abort();
+    }
+    config_watcher_thread.abort();
+    let _ = shutdown_sender.send(true);
+    for thread in network_threads {
+        let _ = thread.await;
     }
-    let fuel_thread = fuel_watcher_result.unwrap();
+    for network in &networks {
+        network.shutdown().await;
+    }
+
+    result
+}
 
-    // start ethereum watcher
-    let ethereum_watcher_result = start_ethereum_watcher(config, actions.clone(), alerts.clone()).await;
-    if ethereum_watcher_result.is_err() {
+// restarts the watchers of each network with the latest config files if they changed, returning the changes. Only the
+// watcher settings (alerts and check schedules) can be reloaded, a config changing anything else (including the list
+// of networks) is rejected as a whole
+fn reload_config(config_files: &[String], networks: &[WatchtowerNetwork]) -> Result<Vec<String>> {
+    let configs = load_config(config_files)?;
+    let names: Vec<&str> = configs.iter().map(|config| config.network_name()).collect();
+    let running: Vec<&str> = networks.iter().map(|network| network.name.as_str()).collect();
+    if names != running {
         return Err(anyhow::anyhow!(
-            "Failed to start ethereum watcher: {}",
-            ethereum_watcher_result.err().unwrap()
+            "Changes to the networks ({} -> {}) can only be applied with a restart.",
+            running.join(", "),
+            names.join(", ")
         ));
     }
-    let ethereum_thread = ethereum_watcher_result.unwrap();
 
-    // wait for threads to finish (if ever)
-    match ethereum_thread.await {
-        Err(e) => {
-            alerts.alert(String::from("Ethereum watcher thread failed."), AlertLevel::Error);
-            return Err(anyhow::anyhow!("Ethereum watcher thread failed: {}", e));
-        }
-        Ok(_) => {}
+    // every network is checked before any is reloaded, so a reload is applied in full or not at all
+    let mut network_changes = Vec::new();
+    for (network, config) in networks.iter().zip(&configs) {
+        network_changes.push(network.diff_config(config)?);
     }
-    match fuel_thread.await {
-        Err(e) => {
-            alerts.alert(String::from("Fuel watcher thread failed."), AlertLevel::Error);
-            return Err(anyhow::anyhow!("Fuel watcher thread failed: {}", e));
+    let mut all_changes = Vec::new();
+    for ((network, config), changes) in networks.iter().zip(configs).zip(network_changes) {
+        if changes.is_empty() {
+            continue;
         }
-        Ok(_) => {}
+        network.alerts.alert(
+            format!(
+                "Reloaded config from {}. Changes: {}",
+                config_files.join(", "),
+                changes.join(", ")
+            ),
+            AlertLevel::Info,
+        );
+        all_changes.extend(
+            changes
+                .into_iter()
+                .map(|change| format!("{}: {}", network.name, change)),
+        );
+        network.reload_config(config);
     }
+    Ok(all_changes)
+}
+
+// resolves once the process is asked to stop
+#[cfg(unix)]
+async fn shutdown_signal() -> Result<&'static str> {
+    use tokio::signal::unix::{signal, SignalKind};
+    let mut terminate = signal(SignalKind::terminate())?;
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => result.map(|_| "SIGINT").map_err(|e| e.into()),
+        _ = terminate.recv() => Ok("SIGTERM"),
+    }
+}
 
-    Ok(())
+// resolves once the process is asked to stop
+#[cfg(not(unix))]
+async fn shutdown_signal() -> Result<&'static str> {
+    tokio::signal::ctrl_c().await?;
+    Ok("ctrl-c")
 }
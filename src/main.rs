@@ -1,40 +1,186 @@
-use std::env;
+use clap::{Parser, Subcommand, ValueEnum};
+use fuel_canary_watchtower::{EthereumAction, WatchtowerConfig};
+use log::LevelFilter;
+use std::process::ExitCode;
 
 pub static WATCHTOWER_CONFIG_FILE: &str = "watchtower_config.json";
 pub static LOGGING_CONFIG_FILE: &str = "logging_config.yaml";
 
+#[derive(Parser)]
+#[command(about = "Watches the Fuel and Ethereum chains and pauses the bridge contracts when something goes wrong")]
+struct Cli {
+    /// Watchtower config file (json, yaml or toml). Can be given more than once, with each file overriding the
+    /// settings of the ones before it
+    #[arg(short, long, global = true, default_value = WATCHTOWER_CONFIG_FILE)]
+    config: Vec<String>,
+
+    /// Logging config file
+    #[arg(long, global = true, default_value = LOGGING_CONFIG_FILE)]
+    log_config: String,
+
+    /// Overrides the root log level of the logging config (off, error, warn, info, debug or trace)
+    #[arg(long, global = true)]
+    log_level: Option<LevelFilter>,
+
+    /// Network to run status, backfill, pause, simulate and actions against. Required when the config lists more than one
+    /// network
+    #[arg(long, global = true)]
+    network: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Runs the watchtower (the default)
+    Run {
+        /// Alert on actions without sending any transactions
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Validates the config file
+    CheckConfig {
+        /// Also checks the config against the live endpoints
+        #[arg(long)]
+        live: bool,
+    },
+    /// Prints the state of both chains, the contracts and the saved progress
+    Status,
+    /// Verifies the state contract commits made in a range of ethereum blocks
+    Backfill {
+        /// First ethereum block to check
+        #[arg(long)]
+        from_block: u64,
+        /// Last ethereum block to check (defaults to the latest block)
+        #[arg(long)]
+        to_block: Option<u64>,
+    },
+    /// Pauses a contract
+    Pause {
+        contract: Contract,
+        /// Only simulates the pause
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Checks pausing a contract would succeed, without sending any transactions
+    Simulate {
+        #[arg(default_value = "all")]
+        contract: Contract,
+    },
+    /// Prints the latest actions from the action log, one json record per line
+    Actions {
+        /// Number of actions to print
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+}
+
+#[derive(Clone, ValueEnum)]
+enum Contract {
+    State,
+    Portal,
+    Gateway,
+    All,
+}
+
+impl From<Contract> for EthereumAction {
+    fn from(contract: Contract) -> Self {
+        match contract {
+            Contract::State => EthereumAction::PauseState,
+            Contract::Portal => EthereumAction::PausePortal,
+            Contract::Gateway => EthereumAction::PauseGateway,
+            Contract::All => EthereumAction::PauseAll,
+        }
+    }
+}
+
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+
     // setup logging
-    log4rs::init_file(LOGGING_CONFIG_FILE, Default::default()).unwrap();
-
-    // determine the config file to use
-    let mut config_file = WATCHTOWER_CONFIG_FILE;
-    let args: Vec<String> = env::args().collect();
-    if args.len() > 1 {
-        let arg = &args[1];
-        if arg.ends_with(".json") {
-            config_file = arg;
-            log::info!("Using config file: {}", config_file);
-        } else {
-            log::info!("Invalid config file specified: {}", arg);
-            log::info!("Using default config file: {}", config_file);
-        }
-    } else {
-        log::info!("Using default config file: {}", config_file);
+    if let Err(e) = init_logging(&cli.log_config, cli.log_level) {
+        eprintln!("Failed to setup logging from {}: {}", cli.log_config, e);
+        return ExitCode::FAILURE;
     }
 
     // get the watchtower config
-    let config_result = fuel_canary_watchtower::load_config(config_file);
-    if config_result.is_err() {
-        log::error!("Failed to load config: {}", config_result.err().unwrap());
-    } else {
-        let config = config_result.unwrap();
-
-        // start the watchtower
-        let run_result = fuel_canary_watchtower::run(&config).await;
-        if run_result.is_err() {
-            log::error!("{}", run_result.err().unwrap());
+    log::info!("Using config files: {}", cli.config.join(", "));
+    let configs = match fuel_canary_watchtower::load_config(&cli.config) {
+        Ok(configs) => configs,
+        Err(e) => {
+            log::error!("Failed to load config: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let result = match cli.command.unwrap_or(Command::Run { dry_run: false }) {
+        Command::Run { dry_run } => fuel_canary_watchtower::run(&configs, &cli.config, dry_run).await,
+        Command::CheckConfig { live } => {
+            let mut result = Ok(());
+            if live {
+                for config in &configs {
+                    result = fuel_canary_watchtower::validate_config_live(config).await;
+                    if result.is_err() {
+                        break;
+                    }
+                }
+            }
+            if result.is_ok() {
+                println!("{} is valid.", cli.config.join(", "));
+            }
+            result
+        }
+        command => match select_network(&configs, &cli.network) {
+            Ok(config) => match command {
+                Command::Status => fuel_canary_watchtower::print_status(config).await,
+                Command::Backfill { from_block, to_block } => {
+                    fuel_canary_watchtower::backfill(config, from_block, to_block).await
+                }
+                Command::Pause { contract, dry_run } => {
+                    fuel_canary_watchtower::pause(config, contract.into(), dry_run).await
+                }
+                Command::Simulate { contract } => fuel_canary_watchtower::simulate(config, contract.into()).await,
+                Command::Actions { limit } => fuel_canary_watchtower::print_actions(config, limit),
+                Command::Run { .. } | Command::CheckConfig { .. } => unreachable!(),
+            },
+            Err(e) => Err(e),
+        },
+    };
+    match result {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(e) => {
+            log::error!("{}", e);
+            ExitCode::FAILURE
         }
     }
 }
+
+// the network a one off command runs against, which can be left out when the config has only one
+fn select_network<'a>(
+    configs: &'a [WatchtowerConfig],
+    network: &Option<String>,
+) -> anyhow::Result<&'a WatchtowerConfig> {
+    let names: Vec<&str> = configs.iter().map(|config| config.network_name()).collect();
+    match network {
+        Some(network) => configs
+            .iter()
+            .find(|config| config.network_name() == network)
+            .ok_or_else(|| anyhow::anyhow!("Unknown network {} (expected one of: {}).", network, names.join(", "))),
+        None if configs.len() == 1 => Ok(&configs[0]),
+        None => Err(anyhow::anyhow!(
+            "The config lists more than one network, pick one with --network ({}).",
+            names.join(", ")
+        )),
+    }
+}
+
+fn init_logging(log_config: &str, log_level: Option<LevelFilter>) -> anyhow::Result<()> {
+    let mut config = log4rs::config::load_config_file(log_config, Default::default())?;
+    if let Some(log_level) = log_level {
+        config.root_mut().set_level(log_level);
+    }
+    log4rs::init_config(config)?;
+    Ok(())
+}
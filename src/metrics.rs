@@ -0,0 +1,297 @@
+use crate::alerts::AlertLevel;
+use crate::config::DEFAULT_NETWORK_NAME;
+use crate::ethereum_watcher::ethereum_client::EthereumEndpointMetrics;
+
+use prometheus_client::encoding::text::{encode, Encode};
+use prometheus_client::metrics::counter::Counter;
+use prometheus_client::metrics::family::Family;
+use prometheus_client::metrics::gauge::Gauge;
+use prometheus_client::registry::Registry;
+use reqwest::Url;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+pub static METRICS_PREFIX: &str = "watchtower";
+pub static NO_CHECK_LABEL: &str = "watchtower";
+
+#[derive(Clone, Hash, PartialEq, Eq, Encode)]
+pub enum Chain {
+    Fuel,
+    Ethereum,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, Encode)]
+pub enum Direction {
+    Deposit,
+    Withdraw,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, Encode)]
+pub enum ActionResult {
+    Attempted,
+    Succeeded,
+    Failed,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, Encode)]
+struct NetworkLabels {
+    network: String,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, Encode)]
+struct AccountLabels {
+    network: String,
+    account: String,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, Encode)]
+struct ChainLabels {
+    network: String,
+    chain: Chain,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, Encode)]
+struct WindowLabels {
+    network: String,
+    direction: Direction,
+    token: String,
+    time_frame: u32,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, Encode)]
+struct AlertLabels {
+    network: String,
+    level: AlertLevel,
+    check: String,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, Encode)]
+struct ActionLabels {
+    network: String,
+    contract: String,
+    result: ActionResult,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, Encode)]
+struct EndpointLabels {
+    network: String,
+    endpoint: String,
+}
+
+// prometheus metrics recorded by the watchers, alerts and actions, and served on /metrics. Every metric is labeled
+// with the network it was recorded for
+#[derive(Clone)]
+pub struct WatchtowerMetrics {
+    registry: Arc<Registry>,
+    network: String,
+    seconds_since_last_block: Family<ChainLabels, Gauge>,
+    window_total: Family<WindowLabels, Gauge<f64, AtomicU64>>,
+    signer_balance: Family<AccountLabels, Gauge<f64, AtomicU64>>,
+    alerts: Family<AlertLabels, Counter>,
+    actions: Family<ActionLabels, Counter>,
+    rpc_requests: Family<EndpointLabels, Counter>,
+    rpc_errors: Family<EndpointLabels, Counter>,
+    rpc_timeouts: Family<EndpointLabels, Counter>,
+    rpc_average_latency: Family<EndpointLabels, Gauge<f64, AtomicU64>>,
+    last_verified_commit_block: Family<NetworkLabels, Gauge>,
+}
+
+impl WatchtowerMetrics {
+    pub fn new() -> Self {
+        let mut registry = <Registry>::with_prefix(METRICS_PREFIX);
+        let seconds_since_last_block = Family::<ChainLabels, Gauge>::default();
+        registry.register(
+            "seconds_since_last_block",
+            "Seconds since the latest block of each chain was produced",
+            Box::new(seconds_since_last_block.clone()),
+        );
+        let window_total = Family::<WindowLabels, Gauge<f64, AtomicU64>>::default();
+        registry.register(
+            "window_total",
+            "Amount bridged (in whole tokens) over the time frame of each deposit and withdraw alert",
+            Box::new(window_total.clone()),
+        );
+        let signer_balance = Family::<AccountLabels, Gauge<f64, AtomicU64>>::default();
+        registry.register(
+            "signer_balance",
+            "Ether balance of each account actions can be sent from",
+            Box::new(signer_balance.clone()),
+        );
+        let alerts = Family::<AlertLabels, Counter>::default();
+        registry.register("alerts", "Alerts emitted per level and check", Box::new(alerts.clone()));
+        let actions = Family::<ActionLabels, Counter>::default();
+        registry.register(
+            "actions",
+            "Contract pauses attempted, succeeded and failed",
+            Box::new(actions.clone()),
+        );
+        let rpc_requests = Family::<EndpointLabels, Counter>::default();
+        registry.register(
+            "rpc_requests",
+            "Requests sent to each ethereum RPC endpoint",
+            Box::new(rpc_requests.clone()),
+        );
+        let rpc_errors = Family::<EndpointLabels, Counter>::default();
+        registry.register(
+            "rpc_errors",
+            "Failed requests (including timeouts) to each ethereum RPC endpoint",
+            Box::new(rpc_errors.clone()),
+        );
+        let rpc_timeouts = Family::<EndpointLabels, Counter>::default();
+        registry.register(
+            "rpc_timeouts",
+            "Timed out requests to each ethereum RPC endpoint",
+            Box::new(rpc_timeouts.clone()),
+        );
+        let rpc_average_latency = Family::<EndpointLabels, Gauge<f64, AtomicU64>>::default();
+        registry.register(
+            "rpc_average_latency_seconds",
+            "Average request latency of each ethereum RPC endpoint",
+            Box::new(rpc_average_latency.clone()),
+        );
+        let last_verified_commit_block = Family::<NetworkLabels, Gauge>::default();
+        registry.register(
+            "last_verified_commit_block",
+            "Ethereum block up to which all state contract commits have been verified",
+            Box::new(last_verified_commit_block.clone()),
+        );
+
+        WatchtowerMetrics {
+            registry: Arc::new(registry),
+            network: DEFAULT_NETWORK_NAME.to_string(),
+            seconds_since_last_block,
+            window_total,
+            signer_balance,
+            alerts,
+            actions,
+            rpc_requests,
+            rpc_errors,
+            rpc_timeouts,
+            rpc_average_latency,
+            last_verified_commit_block,
+        }
+    }
+
+    // metrics recorded through the returned copy are labeled with the given network
+    pub fn for_network(&self, network: &str) -> Self {
+        WatchtowerMetrics {
+            network: network.to_string(),
+            ..self.clone()
+        }
+    }
+
+    pub fn set_seconds_since_last_block(&self, chain: Chain, seconds: u32) {
+        let labels = ChainLabels {
+            network: self.network.clone(),
+            chain,
+        };
+        self.seconds_since_last_block.get_or_create(&labels).set(seconds as u64);
+    }
+
+    pub fn set_window_total(&self, direction: Direction, token: &str, time_frame: u32, amount: f64) {
+        let labels = WindowLabels {
+            network: self.network.clone(),
+            direction,
+            token: token.to_string(),
+            time_frame,
+        };
+        self.window_total.get_or_create(&labels).set(amount);
+    }
+
+    pub fn set_signer_balance(&self, account: &str, balance: f64) {
+        let labels = AccountLabels {
+            network: self.network.clone(),
+            account: account.to_string(),
+        };
+        self.signer_balance.get_or_create(&labels).set(balance);
+    }
+
+    pub fn record_alert(&self, level: &AlertLevel, check: Option<&str>) {
+        let labels = AlertLabels {
+            network: self.network.clone(),
+            level: level.clone(),
+            check: check.unwrap_or(NO_CHECK_LABEL).to_string(),
+        };
+        self.alerts.get_or_create(&labels).inc();
+    }
+
+    pub fn record_action(&self, contract: &str, result: ActionResult) {
+        let labels = ActionLabels {
+            network: self.network.clone(),
+            contract: contract.to_string(),
+            result,
+        };
+        self.actions.get_or_create(&labels).inc();
+    }
+
+    pub fn set_last_verified_commit_block(&self, block_num: u64) {
+        self.last_verified_commit_block
+            .get_or_create(&self.network_labels())
+            .set(block_num);
+    }
+
+    // the rpc client keeps its own running totals, so they are copied over as they are rather than incremented
+    pub fn update_rpc_metrics(&self, endpoint_metrics: &[EthereumEndpointMetrics]) {
+        for metrics in endpoint_metrics {
+            let labels = EndpointLabels {
+                network: self.network.clone(),
+                endpoint: endpoint_label(&metrics.url),
+            };
+            self.rpc_requests
+                .get_or_create(&labels)
+                .inner()
+                .store(metrics.requests, Ordering::Relaxed);
+            self.rpc_errors
+                .get_or_create(&labels)
+                .inner()
+                .store(metrics.errors, Ordering::Relaxed);
+            self.rpc_timeouts
+                .get_or_create(&labels)
+                .inner()
+                .store(metrics.timeouts, Ordering::Relaxed);
+            if metrics.requests > 0 {
+                let average_latency = metrics.total_latency.as_secs_f64() / metrics.requests as f64;
+                self.rpc_average_latency.get_or_create(&labels).set(average_latency);
+            }
+        }
+    }
+
+    pub fn encode(&self) -> Result<String, std::io::Error> {
+        let mut buffer = vec![];
+        encode(&mut buffer, &self.registry)?;
+        Ok(String::from_utf8_lossy(&buffer).into_owned())
+    }
+
+    fn network_labels(&self) -> NetworkLabels {
+        NetworkLabels {
+            network: self.network.clone(),
+        }
+    }
+}
+
+impl Default for WatchtowerMetrics {
+    fn default() -> Self {
+        WatchtowerMetrics::new()
+    }
+}
+
+impl fmt::Debug for WatchtowerMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WatchtowerMetrics")
+            .field("network", &self.network)
+            .finish_non_exhaustive()
+    }
+}
+
+// rpc urls often carry an api key in their path or query, so only the host is used as a label
+fn endpoint_label(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(url) => match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{host}:{port}"),
+            (Some(host), None) => host.to_string(),
+            _ => String::from("unknown"),
+        },
+        Err(_) => String::from("unknown"),
+    }
+}
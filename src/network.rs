@@ -0,0 +1,339 @@
+use crate::alerts::{AlertLevel, WatchtowerAlerts};
+use crate::clients::WatchtowerClients;
+use crate::config::diff_config;
+use crate::ethereum_actions::WatchtowerEthereumActions;
+use crate::ethereum_watcher::start_ethereum_watcher;
+use crate::fuel_watcher::start_fuel_watcher;
+use crate::heartbeat::start_heartbeat;
+use crate::metrics::WatchtowerMetrics;
+use crate::state::WatchtowerState;
+use crate::status::WatchtowerStatus;
+use crate::supervisor::supervise;
+use crate::WatchtowerConfig;
+
+use anyhow::Result;
+use ethers::signers::Signer;
+use std::sync::Arc;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+// a network whose watchers could not be kept running, with the reason
+pub type NetworkStopped = (String, Result<()>);
+
+// everything watching one deployment of the bridge. Networks run side by side in one process but share none of
+// their clients, progress, alerts or actions
+#[derive(Clone, Debug)]
+pub struct WatchtowerNetwork {
+    pub name: String,
+    pub clients: WatchtowerClients,
+    pub status: WatchtowerStatus,
+    pub alerts: WatchtowerAlerts,
+    pub actions: WatchtowerEthereumActions,
+    pub signer_required: bool,
+    pub signer_configured: bool,
+    state: WatchtowerState,
+    config_sender: Arc<watch::Sender<WatchtowerConfig>>,
+}
+
+impl WatchtowerNetwork {
+    // starts the watchers of the network. The returned thread reports on `stopped` if a watcher could not be kept
+    // running, and finishes once both watchers have stopped
+    pub async fn start(
+        config: &WatchtowerConfig,
+        metrics: &WatchtowerMetrics,
+        shutdown: watch::Receiver<bool>,
+        stopped: UnboundedSender<NetworkStopped>,
+        dry_run: bool,
+    ) -> Result<(Self, JoinHandle<()>)> {
+        let name = config.network_name().to_string();
+        let log_tag = config.log_tag();
+
+        // build alerts service
+        let metrics = metrics.for_network(&name);
+        let alerts = match WatchtowerAlerts::new(config, metrics.clone()) {
+            Ok(alerts) => alerts,
+            Err(e) => return Err(anyhow::anyhow!("{}Failed to setup alerts: {}", log_tag, e)),
+        };
+
+        // build clients shared by all services of the network
+        let clients = match WatchtowerClients::new(config).await {
+            Ok(clients) => clients,
+            Err(e) => return Err(anyhow::anyhow!("{}Failed to setup clients: {}", log_tag, e)),
+        };
+
+        // load progress saved by a previous run
+        let state = match WatchtowerState::load(config) {
+            Ok(state) => state,
+            Err(e) => return Err(anyhow::anyhow!("{}Failed to load state: {}", log_tag, e)),
+        };
+
+        // build ethereum actions service
+        let actions =
+            match WatchtowerEthereumActions::new(config, &clients, alerts.clone(), metrics.clone(), dry_run).await {
+                Ok(actions) => actions,
+                Err(e) => return Err(anyhow::anyhow!("{}Failed to setup actions: {}", log_tag, e)),
+            };
+
+        // make clear up front whether the watchtower can act on what it finds
+        let accounts: Vec<String> = clients
+            .signers
+            .iter()
+            .map(|signer| format!("{:?}", signer.address()))
+            .collect();
+        let (capabilities, level) = if accounts.is_empty() && config.has_alert_actions() {
+            (
+                String::from("watch and alert only (no ethereum account, the actions set on alerts are disabled)"),
+                AlertLevel::Warn,
+            )
+        } else if accounts.is_empty() {
+            (
+                String::from("watch and alert only (no ethereum account)"),
+                AlertLevel::Info,
+            )
+        } else if let Some(safe) = &clients.safe_contract {
+            let owners: Vec<String> = safe
+                .get_owner_addresses()
+                .iter()
+                .map(|owner| format!("{owner:?}"))
+                .collect();
+            let capabilities = if dry_run {
+                format!(
+                    "watch and alert (dry run, nothing is proposed to Safe {:?} by {})",
+                    safe.get_address(),
+                    owners.join(", ")
+                )
+            } else {
+                format!(
+                    "watch, alert and propose pauses to Safe {:?} signed by {} (in that order)",
+                    safe.get_address(),
+                    owners.join(", ")
+                )
+            };
+            (capabilities, AlertLevel::Info)
+        } else if dry_run {
+            (
+                format!(
+                    "watch and alert (dry run, nothing is sent from {})",
+                    accounts.join(", ")
+                ),
+                AlertLevel::Info,
+            )
+        } else {
+            (
+                format!(
+                    "watch, alert and pause contracts from {} (in that order)",
+                    accounts.join(", ")
+                ),
+                AlertLevel::Info,
+            )
+        };
+        alerts.alert(format!("Capabilities: {capabilities}."), level);
+
+        let (network, watcher_config) =
+            WatchtowerNetwork::new(config, clients.clone(), state.clone(), alerts.clone(), actions.clone());
+        let status = network.status.clone();
+
+        // start fuel watcher (restarted if it ever fails or the config is reloaded)
+        let fuel_clients = clients.clone();
+        let fuel_status = status.clone();
+        let fuel_metrics = metrics.clone();
+        let fuel_actions = actions.clone();
+        let fuel_alerts = alerts.clone();
+        let mut fuel_thread = supervise(
+            "Fuel watcher",
+            config.max_watcher_restarts,
+            alerts.clone(),
+            status.clone(),
+            shutdown.clone(),
+            watcher_config.clone(),
+            move |config| {
+                let clients = fuel_clients.clone();
+                let status = fuel_status.clone();
+                let metrics = fuel_metrics.clone();
+                let actions = fuel_actions.clone();
+                let alerts = fuel_alerts.clone();
+                async move { start_fuel_watcher(&config, &clients, &status, &metrics, actions, alerts).await }
+            },
+        );
+
+        // start ethereum watcher (restarted if it ever fails or the config is reloaded)
+        let ethereum_clients = clients.clone();
+        let ethereum_state = state.clone();
+        let ethereum_status = status.clone();
+        let ethereum_metrics = metrics.clone();
+        let ethereum_actions = actions.clone();
+        let ethereum_alerts = alerts.clone();
+        let mut ethereum_thread = supervise(
+            "Ethereum watcher",
+            config.max_watcher_restarts,
+            alerts.clone(),
+            status.clone(),
+            shutdown,
+            watcher_config,
+            move |config| {
+                let clients = ethereum_clients.clone();
+                let state = ethereum_state.clone();
+                let status = ethereum_status.clone();
+                let metrics = ethereum_metrics.clone();
+                let actions = ethereum_actions.clone();
+                let alerts = ethereum_alerts.clone();
+                async move { start_ethereum_watcher(&config, &clients, &state, &status, &metrics, actions, alerts).await }
+            },
+        );
+
+        // start heartbeat to the external monitor
+        let heartbeat_thread = match &config.heartbeat {
+            Some(heartbeat_config) => match start_heartbeat(heartbeat_config, status.clone(), log_tag.clone()) {
+                Ok(handle) => Some(handle),
+                Err(e) => return Err(anyhow::anyhow!("{}Failed to start heartbeat: {}", log_tag, e)),
+            },
+            None => None,
+        };
+
+        // report the first watcher to stop, then wait for the other one to be shut down as well
+        let network_name = name.clone();
+        let thread = tokio::spawn(async move {
+            let result = tokio::select! {
+                result = &mut fuel_thread => {
+                    let result = watcher_result("Fuel", result);
+                    let _ = stopped.send((network_name, result));
+                    ethereum_thread.await
+                }
+                result = &mut ethereum_thread => {
+                    let result = watcher_result("Ethereum", result);
+                    let _ = stopped.send((network_name, result));
+                    fuel_thread.await
+                }
+            };
+            if let Err(e) = result {
+                log::error!("{}Watcher supervisor failed: {}", log_tag, e);
+            }
+            if let Some(heartbeat_thread) = heartbeat_thread {
+                heartbeat_thread.abort();
+            }
+        });
+
+        Ok((network, thread))
+    }
+
+    // the network of the given config and clients, before any of its watchers have started. The returned receiver
+    // follows the config the watchers are to run with
+    pub fn new(
+        config: &WatchtowerConfig,
+        clients: WatchtowerClients,
+        state: WatchtowerState,
+        alerts: WatchtowerAlerts,
+        actions: WatchtowerEthereumActions,
+    ) -> (Self, watch::Receiver<WatchtowerConfig>) {
+        let (config_sender, watcher_config) = watch::channel(config.clone());
+        let network = WatchtowerNetwork {
+            name: config.network_name().to_string(),
+            clients,
+            status: WatchtowerStatus::new(),
+            alerts,
+            actions,
+            signer_required: config.has_alert_actions(),
+            signer_configured: config.has_signer(),
+            state,
+            config_sender: Arc::new(config_sender),
+        };
+        (network, watcher_config)
+    }
+
+    // fails if an account of this network also pauses for another network on the same ethereum chain, where the two
+    // would send their pauses with the same nonces. Sharing an account across chains is fine
+    pub fn check_accounts_not_shared(&self, others: &[WatchtowerNetwork]) -> Result<()> {
+        let chain_id = self.clients.ethereum_chain.get_chain_id();
+        for other in others {
+            if other.clients.ethereum_chain.get_chain_id() != chain_id {
+                continue;
+            }
+            for signer in &self.clients.signers {
+                if other
+                    .clients
+                    .signers
+                    .iter()
+                    .any(|other_signer| other_signer.address() == signer.address())
+                {
+                    return Err(anyhow::anyhow!(
+                        "Network {} uses account {:?} on ethereum chain {} just like network {}, each network on a chain needs its own accounts.",
+                        self.name,
+                        signer.address(),
+                        chain_id,
+                        other.name
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // lists the changes the given config would make to the running one, failing if any need a restart
+    pub fn diff_config(&self, config: &WatchtowerConfig) -> Result<Vec<String>> {
+        diff_config(&self.config_sender.borrow(), config)
+    }
+
+    // restarts the watchers with the given config
+    pub fn reload_config(&self, config: WatchtowerConfig) {
+        self.config_sender.send_replace(config);
+    }
+
+    // lets in-flight actions finish, saves progress and pushes out the last alerts. Called once the watchers stopped
+    pub async fn shutdown(&self) {
+        if let Err(e) = self.actions.shutdown().await {
+            self.alerts.alert(e.to_string(), AlertLevel::Error);
+        }
+        if let Err(e) = self.state.save() {
+            self.alerts
+                .alert(format!("Failed to save state: {e}"), AlertLevel::Error);
+        }
+        self.alerts.flush().await;
+    }
+}
+
+fn watcher_result(name: &str, result: Result<Result<()>, tokio::task::JoinError>) -> Result<()> {
+    match result {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(e)) => Err(anyhow::anyhow!("{} watcher stopped: {}", name, e)),
+        Err(e) => Err(anyhow::anyhow!("{} watcher supervisor failed: {}", name, e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{start_test_network, test_config};
+
+    fn network_config(name: &str, wallet_key: &str) -> WatchtowerConfig {
+        let mut config = test_config();
+        config.network = Some(name.to_string());
+        config.ethereum_wallet_key = vec![wallet_key.to_string()];
+        config
+    }
+
+    #[tokio::test]
+    async fn networks_on_one_chain_cannot_share_accounts() {
+        let key = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+        let other_key = "0123456789012345678901234567890123456789012345678901234567890123";
+        let testnet = start_test_network(network_config("testnet", key)).await;
+        let devnet = start_test_network(network_config("devnet", key)).await;
+        let other_devnet = start_test_network(network_config("devnet", other_key)).await;
+
+        let error = devnet
+            .network
+            .check_accounts_not_shared(std::slice::from_ref(&testnet.network))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Network devnet uses account {:?} on ethereum chain 5 just like network testnet, each network on a chain needs its own accounts.",
+                testnet.network.clients.signers[0].address()
+            )
+        );
+        assert!(other_devnet
+            .network
+            .check_accounts_not_shared(std::slice::from_ref(&testnet.network))
+            .is_ok());
+    }
+}
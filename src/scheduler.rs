@@ -0,0 +1,247 @@
+use crate::alerts::{AlertLevel, WatchtowerAlerts};
+use crate::ethereum_actions::{EthereumAction, WatchtowerEthereumActions};
+use crate::status::WatchtowerStatus;
+
+use anyhow::Result;
+use std::cmp::max;
+use std::future::Future;
+use std::time::{Duration, SystemTime};
+use tokio::sync::watch;
+use tokio::task::JoinSet;
+
+pub static DEFAULT_CHECK_TIMEOUT: Duration = Duration::from_millis(60000);
+
+#[derive(Clone, Debug)]
+pub struct CheckSchedule {
+    interval: Duration,
+    timeout: Duration,
+    trigger: Option<(watch::Receiver<Option<u64>>, Duration)>,
+}
+
+impl CheckSchedule {
+    pub fn new(interval: Option<u64>, timeout: Option<u64>, default_interval: Duration) -> Self {
+        CheckSchedule {
+            interval: interval.map(Duration::from_secs).unwrap_or(default_interval),
+            timeout: timeout.map(Duration::from_secs).unwrap_or(DEFAULT_CHECK_TIMEOUT),
+            trigger: None,
+        }
+    }
+
+    // also runs the check whenever the trigger fires, polling no more often than `live_interval` while the trigger
+    // is live (holds a value)
+    pub fn with_trigger(mut self, trigger: Option<watch::Receiver<Option<u64>>>, live_interval: Duration) -> Self {
+        self.trigger = trigger.map(|trigger| (trigger, live_interval));
+        self
+    }
+
+    // longest time between successful runs of a healthy check
+    fn max_age(&self) -> Duration {
+        let interval = match &self.trigger {
+            Some((_, live_interval)) => max(self.interval, *live_interval),
+            None => self.interval,
+        };
+        interval + self.timeout
+    }
+
+    async fn wait(&mut self) {
+        match &mut self.trigger {
+            Some((trigger, live_interval)) => {
+                let interval = match *trigger.borrow() {
+                    Some(_) => max(self.interval, *live_interval),
+                    None => self.interval,
+                };
+                tokio::select! {
+                    _ = tokio::time::sleep(interval) => {}
+                    changed = trigger.changed() => {
+                        if changed.is_err() {
+                            tokio::time::sleep(interval).await;
+                        }
+                    }
+                }
+            }
+            None => tokio::time::sleep(self.interval).await,
+        }
+    }
+}
+
+// runs each check as its own task so a slow check never holds up the others
+pub struct CheckScheduler {
+    watcher: &'static str,
+    checks: JoinSet<()>,
+    check_names: Vec<String>,
+    status: WatchtowerStatus,
+    alerts: WatchtowerAlerts,
+    actions: WatchtowerEthereumActions,
+}
+
+impl CheckScheduler {
+    // `watcher` is the name the watcher is supervised under, so its checks are reported along with its running state
+    pub fn new(
+        watcher: &'static str,
+        status: WatchtowerStatus,
+        alerts: WatchtowerAlerts,
+        actions: WatchtowerEthereumActions,
+    ) -> Self {
+        CheckScheduler {
+            watcher,
+            checks: JoinSet::new(),
+            check_names: vec![],
+            status,
+            alerts,
+            actions,
+        }
+    }
+
+    // a check that runs past its timeout is cancelled and reported with the given alert level and action. Checks
+    // handle their own alerts and return an error only to record that they failed to run
+    pub fn schedule<F, Fut>(
+        &mut self,
+        name: String,
+        mut schedule: CheckSchedule,
+        alert_level: AlertLevel,
+        alert_action: EthereumAction,
+        check: F,
+    ) where
+        F: Fn() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let watcher = self.watcher;
+        let status = self.status.clone();
+        let alerts = self.alerts.for_check(&name);
+        let actions = self.actions.for_check(&name);
+        status.register_check(watcher, &name, schedule.max_age());
+        self.check_names.push(name.clone());
+        self.checks.spawn(async move {
+            loop {
+                let started = SystemTime::now();
+                match tokio::time::timeout(schedule.timeout, check()).await {
+                    Ok(Ok(_)) => {
+                        status.record_success(watcher, &name);
+                        alerts.resolve(started);
+                    }
+                    Ok(Err(e)) => {
+                        status.record_failure(watcher, &name, e.to_string());
+                        alerts.resolve(started);
+                    }
+                    Err(_) => {
+                        let error = format!(
+                            "Check for {} timed out after {} seconds.",
+                            name,
+                            schedule.timeout.as_secs()
+                        );
+                        alerts.alert(error.clone(), alert_level.clone());
+                        actions.action(alert_action.clone(), Some(alert_level.clone()));
+                        status.record_failure(watcher, &name, error);
+                    }
+                }
+                schedule.wait().await;
+            }
+        });
+    }
+
+    // checks run forever, so this only returns if one of them crashed
+    pub async fn run(mut self) {
+        // checks of an earlier run that were not scheduled again are forgotten
+        self.status.retain_checks(self.watcher, &self.check_names);
+        if let Some(Err(e)) = self.checks.join_next().await {
+            panic!("Scheduled check crashed: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{start_actions, TestContract};
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn timed_out_checks_alert_and_act() {
+        let contract = Arc::new(TestContract::default());
+        let (actions, alerts) = start_actions(contract.clone());
+        let status = WatchtowerStatus::new();
+
+        let mut scheduler = CheckScheduler::new("test", status.clone(), alerts.clone(), actions.clone());
+        scheduler.schedule(
+            String::from("stuck check"),
+            CheckSchedule::new(Some(3600), Some(5), Duration::ZERO),
+            AlertLevel::Error,
+            EthereumAction::PauseState,
+            std::future::pending,
+        );
+        tokio::spawn(scheduler.run());
+        while status.get_checks()[0].last_error.is_none() {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        let error = "Check for stuck check timed out after 5 seconds.";
+        let check_status = &status.get_checks()[0];
+        assert_eq!(check_status.last_error.as_deref(), Some(error));
+        assert_eq!(check_status.last_success, None);
+        let active_alerts = alerts.get_active_alerts();
+        assert_eq!(active_alerts.len(), 1);
+        assert_eq!(active_alerts[0].check, "stuck check");
+        assert_eq!(active_alerts[0].level, AlertLevel::Error);
+        assert_eq!(active_alerts[0].text, error);
+        actions.shutdown().await.unwrap();
+        assert_eq!(contract.pauses_sent.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn failed_checks_are_recorded_without_acting() {
+        let contract = Arc::new(TestContract::default());
+        let (actions, alerts) = start_actions(contract.clone());
+        let status = WatchtowerStatus::new();
+
+        let mut scheduler = CheckScheduler::new("test", status.clone(), alerts.clone(), actions.clone());
+        scheduler.schedule(
+            String::from("failing check"),
+            CheckSchedule::new(Some(3600), Some(5), Duration::ZERO),
+            AlertLevel::Error,
+            EthereumAction::PauseState,
+            || async { Err(anyhow::anyhow!("no endpoint")) },
+        );
+        tokio::spawn(scheduler.run());
+        while status.get_checks()[0].last_error.is_none() {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        assert_eq!(status.get_checks()[0].last_error.as_deref(), Some("no endpoint"));
+        assert!(alerts.get_active_alerts().is_empty());
+        actions.shutdown().await.unwrap();
+        assert_eq!(contract.pauses_sent.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn restarted_schedulers_replace_their_checks() {
+        let (actions, alerts) = start_actions(Arc::new(TestContract::default()));
+        let status = WatchtowerStatus::new();
+        status.register_check("test", "removed check", Duration::ZERO);
+        status.register_check("other", "other check", Duration::ZERO);
+
+        let mut scheduler = CheckScheduler::new("test", status.clone(), alerts, actions);
+        scheduler.schedule(
+            String::from("new check"),
+            CheckSchedule::new(Some(3600), Some(5), Duration::ZERO),
+            AlertLevel::None,
+            EthereumAction::None,
+            || async { Ok(()) },
+        );
+        tokio::spawn(scheduler.run());
+        tokio::task::yield_now().await;
+
+        let checks: Vec<(String, String)> = status
+            .get_checks()
+            .into_iter()
+            .map(|check| (check.watcher, check.check))
+            .collect();
+        assert_eq!(
+            checks,
+            vec![
+                (String::from("other"), String::from("other check")),
+                (String::from("test"), String::from("new check"))
+            ]
+        );
+    }
+}
@@ -0,0 +1,323 @@
+use crate::admin::WatchtowerAdmin;
+use crate::config::HttpServerConfig;
+use crate::metrics::WatchtowerMetrics;
+use crate::network::WatchtowerNetwork;
+
+use anyhow::Result;
+use futures::future::join_all;
+use hyper::header::{HeaderValue, CONTENT_TYPE};
+use hyper::server::conn::AddrStream;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde_json::{json, Map, Value};
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::time::{Duration, UNIX_EPOCH};
+use tokio::task::JoinHandle;
+
+pub static READY_CHECK_TIMEOUT: Duration = Duration::from_millis(5000);
+
+#[derive(Clone, Debug)]
+pub struct WatchtowerServer {
+    networks: Vec<WatchtowerNetwork>,
+    metrics: WatchtowerMetrics,
+    admin: Option<WatchtowerAdmin>,
+}
+
+impl WatchtowerServer {
+    pub fn new(networks: Vec<WatchtowerNetwork>, metrics: WatchtowerMetrics) -> Self {
+        WatchtowerServer {
+            networks,
+            metrics,
+            admin: None,
+        }
+    }
+
+    // also serves the admin api under /admin/
+    pub fn with_admin(mut self, admin: WatchtowerAdmin) -> Self {
+        self.admin = Some(admin);
+        self
+    }
+
+    // starts a thread that serves the health and metrics endpoints
+    pub fn start(self, config: &HttpServerConfig) -> Result<JoinHandle<()>> {
+        let address: SocketAddr = config.address.parse()?;
+        let make_service = make_service_fn(move |connection: &AddrStream| {
+            let server = self.clone();
+            let remote_address = connection.remote_addr();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    server.clone().handle(request, remote_address)
+                }))
+            }
+        });
+        let http_server = Server::try_bind(&address)?.serve(make_service);
+        log::info!("Serving health and metrics endpoints on {}", address);
+
+        let handle = tokio::spawn(async move {
+            if let Err(e) = http_server.await {
+                log::error!("HTTP server failed: {}", e);
+            }
+        });
+
+        Ok(handle)
+    }
+
+    async fn handle(self, request: Request<Body>, remote_address: SocketAddr) -> Result<Response<Body>, Infallible> {
+        if request.method() == Method::GET && request.uri().path() == "/metrics" {
+            return Ok(self.metrics());
+        }
+        if let Some(admin) = &self.admin {
+            if request.uri().path().starts_with("/admin/") {
+                return Ok(admin.handle(request, remote_address).await);
+            }
+        }
+        let response = match (request.method(), request.uri().path()) {
+            (&Method::GET, "/healthz") => self.healthz(),
+            (&Method::GET, "/readyz") => self.readyz().await,
+            _ => (StatusCode::NOT_FOUND, json!({ "error": "not found" })),
+        };
+        Ok(json_response(response))
+    }
+
+    // prometheus text format of all metrics, with the rpc clients' counters brought up to date first
+    fn metrics(&self) -> Response<Body> {
+        for network in &self.networks {
+            self.metrics
+                .for_network(&network.name)
+                .update_rpc_metrics(&network.clients.ethereum_chain.get_rpc_metrics());
+        }
+        match self.metrics.encode() {
+            Ok(text) => {
+                let mut response = Response::new(Body::from(text));
+                response.headers_mut().insert(
+                    CONTENT_TYPE,
+                    HeaderValue::from_static("application/openmetrics-text; version=1.0.0; charset=utf-8"),
+                );
+                response
+            }
+            Err(e) => json_response((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json!({ "error": format!("Failed to encode metrics: {e}") }),
+            )),
+        }
+    }
+
+    // alive as long as every watcher of every network is running
+    fn healthz(&self) -> (StatusCode, Value) {
+        let mut networks = Map::new();
+        for network in &self.networks {
+            networks.insert(network.name.clone(), network_health(network));
+        }
+        let healthy = networks.values().all(|network| network["healthy"] == true);
+        let details = json!({
+            "healthy": healthy,
+            "networks": networks,
+        });
+        (status_code(healthy), details)
+    }
+
+    // ready when every network is ready
+    async fn readyz(&self) -> (StatusCode, Value) {
+        let readiness = join_all(self.networks.iter().map(network_readiness)).await;
+        let mut networks = Map::new();
+        for (network, details) in self.networks.iter().zip(readiness) {
+            networks.insert(network.name.clone(), details);
+        }
+        let ready = networks.values().all(|network| network["ready"] == true);
+        let details = json!({
+            "ready": ready,
+            "networks": networks,
+        });
+        (status_code(ready), details)
+    }
+}
+
+// alive as long as every watcher is running
+fn network_health(network: &WatchtowerNetwork) -> Value {
+    let watchers = network.status.get_watchers();
+    let healthy = !watchers.is_empty() && watchers.values().all(|running| *running);
+    json!({
+        "healthy": healthy,
+        "watchers": watchers,
+    })
+}
+
+// ready when both watchers are running, both chains are reachable, a signer is set up if any alert can take action and
+// every check has succeeded recently
+async fn network_readiness(network: &WatchtowerNetwork) -> Value {
+    let (fuel_connection, ethereum_connection) = tokio::join!(
+        connection_details(network.clients.fuel_chain.check_connection()),
+        connection_details(network.clients.ethereum_chain.check_connection()),
+    );
+    let checks: Vec<Value> = network
+        .status
+        .get_checks()
+        .iter()
+        .map(|check| {
+            json!({
+                "watcher": check.watcher,
+                "check": check.check,
+                "healthy": !check.is_stale(),
+                "last_success": check
+                    .last_success
+                    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                    .map(|time| time.as_secs()),
+                "last_error": check.last_error,
+            })
+        })
+        .collect();
+
+    let watchers = network.status.get_watchers();
+    let watchers_ready = !watchers.is_empty() && watchers.values().all(|running| *running);
+    let signer_ready = network.signer_configured || !network.signer_required;
+    let checks_ready = !checks.is_empty() && checks.iter().all(|check| check["healthy"] == true);
+    let ready = watchers_ready
+        && fuel_connection["connected"] == true
+        && ethereum_connection["connected"] == true
+        && signer_ready
+        && checks_ready;
+    json!({
+        "ready": ready,
+        "watchers": watchers,
+        "fuel_connection": fuel_connection,
+        "ethereum_connection": ethereum_connection,
+        "signer": {
+            "required": network.signer_required,
+            "configured": network.signer_configured,
+        },
+        "checks": checks,
+    })
+}
+
+async fn connection_details(check_connection: impl Future<Output = Result<()>>) -> Value {
+    match tokio::time::timeout(READY_CHECK_TIMEOUT, check_connection).await {
+        Ok(Ok(_)) => json!({ "connected": true }),
+        Ok(Err(e)) => json!({ "connected": false, "error": e.to_string() }),
+        Err(_) => json!({ "connected": false, "error": "timed out" }),
+    }
+}
+
+fn status_code(ok: bool) -> StatusCode {
+    if ok {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+pub fn json_response((status, body): (StatusCode, Value)) -> Response<Body> {
+    let mut response = Response::new(Body::from(body.to_string()));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::Chain;
+    use crate::test_utils::{start_test_network, test_config};
+
+    async fn get(server: &WatchtowerServer, path: &str) -> (StatusCode, String) {
+        let request = Request::get(path).body(Body::empty()).unwrap();
+        let response = server
+            .clone()
+            .handle(request, ([127, 0, 0, 1], 0).into())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, String::from_utf8_lossy(&body).to_string())
+    }
+
+    async fn get_json(server: &WatchtowerServer, path: &str) -> (StatusCode, Value) {
+        let (status, body) = get(server, path).await;
+        (status, serde_json::from_str(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn readyz_needs_running_watchers_and_healthy_checks() {
+        let mut config = test_config();
+        config.ethereum_wallet_key = vec![String::from(
+            "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318",
+        )];
+        let test_network = start_test_network(config).await;
+        let status = test_network.network.status.clone();
+        let server = WatchtowerServer::new(vec![test_network.network.clone()], WatchtowerMetrics::new());
+        status.set_watcher_running("Fuel watcher", true);
+        status.set_watcher_running("Ethereum watcher", true);
+        status.register_check("Fuel watcher", "Block production", Duration::from_secs(60));
+        status.record_success("Fuel watcher", "Block production");
+
+        let (code, details) = get_json(&server, "/readyz").await;
+        assert_eq!(code, StatusCode::OK, "{details}");
+        assert_eq!(details["networks"]["default"]["fuel_connection"]["connected"], true);
+        assert_eq!(details["networks"]["default"]["ethereum_connection"]["connected"], true);
+
+        // a watcher waiting to be restarted
+        status.set_watcher_running("Ethereum watcher", false);
+        let (code, details) = get_json(&server, "/readyz").await;
+        assert_eq!(code, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(details["networks"]["default"]["watchers"]["Ethereum watcher"], false);
+        let (code, _) = get_json(&server, "/healthz").await;
+        assert_eq!(code, StatusCode::SERVICE_UNAVAILABLE);
+
+        // a check that is overdue
+        status.set_watcher_running("Ethereum watcher", true);
+        status.register_check("Ethereum watcher", "Block production", Duration::ZERO);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let (code, details) = get_json(&server, "/readyz").await;
+        assert_eq!(code, StatusCode::SERVICE_UNAVAILABLE);
+        let checks = details["networks"]["default"]["checks"].as_array().unwrap();
+        let unhealthy: Vec<&Value> = checks.iter().filter(|check| check["healthy"] == false).collect();
+        assert_eq!(unhealthy.len(), 1);
+        assert_eq!(unhealthy[0]["watcher"], "Ethereum watcher");
+        let (code, _) = get_json(&server, "/healthz").await;
+        assert_eq!(code, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn metrics_render_the_registry() {
+        let test_network = start_test_network(test_config()).await;
+        let metrics = WatchtowerMetrics::new();
+        metrics
+            .for_network("default")
+            .set_seconds_since_last_block(Chain::Ethereum, 12);
+        let server = WatchtowerServer::new(vec![test_network.network.clone()], metrics);
+
+        let request = Request::get("/metrics").body(Body::empty()).unwrap();
+        let response = server
+            .clone()
+            .handle(request, ([127, 0, 0, 1], 0).into())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[CONTENT_TYPE],
+            "application/openmetrics-text; version=1.0.0; charset=utf-8"
+        );
+        let (_, text) = get(&server, "/metrics").await;
+        assert!(text.contains(r#"watchtower_seconds_since_last_block{network="default",chain="Ethereum"} 12"#));
+        // the rpc counters are brought up to date with the requests the clients made while setting up
+        let endpoint = test_network.ethereum.url.trim_start_matches("http://");
+        let requests = test_network.ethereum.requests().len();
+        let rpc_requests =
+            format!(r#"watchtower_rpc_requests_total{{network="default",endpoint="{endpoint}"}} {requests}"#);
+        assert!(text.contains(&rpc_requests), "{text}");
+        assert!(text.ends_with("# EOF\n"));
+    }
+
+    #[tokio::test]
+    async fn unknown_paths_are_not_found() {
+        let test_network = start_test_network(test_config()).await;
+        let server = WatchtowerServer::new(vec![test_network.network.clone()], WatchtowerMetrics::new());
+
+        let (code, details) = get_json(&server, "/status").await;
+        assert_eq!(code, StatusCode::NOT_FOUND);
+        assert_eq!(details["error"], "not found");
+    }
+}
@@ -0,0 +1,64 @@
+use crate::WatchtowerConfig;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+// progress kept across watcher restarts and persisted to the state file (if configured) on shutdown
+#[derive(Clone, Debug)]
+pub struct WatchtowerState {
+    file_path: Option<String>,
+    last_commit_check_block: Arc<AtomicU64>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+struct StateFile {
+    last_commit_check_block: Option<u64>,
+}
+
+impl WatchtowerState {
+    pub fn load(config: &WatchtowerConfig) -> Result<Self> {
+        let file_path = config.state_file.clone();
+        let state_file = match &file_path {
+            Some(file_path) if Path::new(file_path).exists() => {
+                serde_json::from_str::<StateFile>(&fs::read_to_string(file_path)?)?
+            }
+            _ => StateFile::default(),
+        };
+
+        Ok(WatchtowerState {
+            file_path,
+            last_commit_check_block: Arc::new(AtomicU64::new(state_file.last_commit_check_block.unwrap_or(0))),
+        })
+    }
+
+    pub fn get_last_commit_check_block(&self) -> Option<u64> {
+        match self.last_commit_check_block.load(Ordering::SeqCst) {
+            0 => None,
+            block_num => Some(block_num),
+        }
+    }
+
+    pub fn set_last_commit_check_block(&self, block_num: u64) {
+        self.last_commit_check_block.store(block_num, Ordering::SeqCst);
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let file_path = match &self.file_path {
+            Some(file_path) => file_path,
+            None => return Ok(()),
+        };
+        let state_file = StateFile {
+            last_commit_check_block: self.get_last_commit_check_block(),
+        };
+
+        // write to a temporary file first so a failed write never corrupts the existing state
+        let tmp_file_path = format!("{file_path}.tmp");
+        fs::write(&tmp_file_path, serde_json::to_string_pretty(&state_file)?)?;
+        fs::rename(&tmp_file_path, file_path)?;
+        Ok(())
+    }
+}
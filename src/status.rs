@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+// latest results of every scheduled check, used to report on the health of the watchtower itself
+#[derive(Clone, Debug, Default)]
+pub struct WatchtowerStatus {
+    watchers: Arc<Mutex<BTreeMap<String, bool>>>,
+    checks: Arc<Mutex<BTreeMap<(String, String), CheckStatus>>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct CheckStatus {
+    pub watcher: String,
+    pub check: String,
+    pub max_age: Duration,
+    pub registered: SystemTime,
+    pub last_success: Option<SystemTime>,
+    pub last_error: Option<String>,
+}
+
+impl CheckStatus {
+    // a check is stale when it has not succeeded within the time it should have run again (counted from when it was
+    // registered if it never has)
+    pub fn is_stale(&self) -> bool {
+        let since = self.last_success.unwrap_or(self.registered);
+        match SystemTime::now().duration_since(since) {
+            Ok(age) => age > self.max_age,
+            Err(_) => false,
+        }
+    }
+
+    // unlike not being stale, this needs the check to have succeeded at least once
+    pub fn succeeded_recently(&self) -> bool {
+        match self
+            .last_success
+            .map(|last_success| SystemTime::now().duration_since(last_success))
+        {
+            Some(Ok(age)) => age <= self.max_age,
+            Some(Err(_)) => true,
+            None => false,
+        }
+    }
+}
+
+impl WatchtowerStatus {
+    pub fn new() -> Self {
+        WatchtowerStatus::default()
+    }
+
+    pub fn set_watcher_running(&self, watcher: &str, running: bool) {
+        self.watchers.lock().unwrap().insert(watcher.to_string(), running);
+    }
+
+    pub fn get_watchers(&self) -> BTreeMap<String, bool> {
+        self.watchers.lock().unwrap().clone()
+    }
+
+    // a check registered again (when its watcher restarts) keeps its last results, so a watcher restarting over and
+    // over does not look like it just started
+    pub fn register_check(&self, watcher: &str, check: &str, max_age: Duration) {
+        let mut checks = self.checks.lock().unwrap();
+        let check_status = checks
+            .entry((watcher.to_string(), check.to_string()))
+            .or_insert_with(|| CheckStatus {
+                watcher: watcher.to_string(),
+                check: check.to_string(),
+                max_age,
+                registered: SystemTime::now(),
+                last_success: None,
+                last_error: None,
+            });
+        check_status.max_age = max_age;
+    }
+
+    // forgets the checks of a watcher other than the given ones, so that checks renamed or disabled by a reload do not
+    // stay stale once the watcher restarts
+    pub fn retain_checks(&self, watcher: &str, checks: &[String]) {
+        self.checks
+            .lock()
+            .unwrap()
+            .retain(|(check_watcher, check), _| check_watcher != watcher || checks.contains(check));
+    }
+
+    pub fn record_success(&self, watcher: &str, check: &str) {
+        let mut checks = self.checks.lock().unwrap();
+        if let Some(check_status) = checks.get_mut(&(watcher.to_string(), check.to_string())) {
+            check_status.last_success = Some(SystemTime::now());
+            check_status.last_error = None;
+        }
+    }
+
+    pub fn record_failure(&self, watcher: &str, check: &str, error: String) {
+        let mut checks = self.checks.lock().unwrap();
+        if let Some(check_status) = checks.get_mut(&(watcher.to_string(), check.to_string())) {
+            check_status.last_error = Some(error);
+        }
+    }
+
+    pub fn get_checks(&self) -> Vec<CheckStatus> {
+        self.checks.lock().unwrap().values().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_checks_are_not_stale_until_they_are_overdue() {
+        let status = WatchtowerStatus::new();
+        status.register_check("ethereum", "Block production", Duration::from_secs(60));
+        status.register_check("ethereum", "Account funds", Duration::ZERO);
+        std::thread::sleep(Duration::from_millis(10));
+
+        let stale_checks: Vec<CheckStatus> = status
+            .get_checks()
+            .into_iter()
+            .filter(|check| check.is_stale())
+            .collect();
+        assert_eq!(stale_checks.len(), 1);
+        assert_eq!(stale_checks[0].check, "Account funds");
+    }
+
+    #[test]
+    fn checks_not_retained_are_forgotten() {
+        let status = WatchtowerStatus::new();
+        status.register_check("ethereum", "Old check", Duration::ZERO);
+        status.register_check("ethereum", "Kept check", Duration::ZERO);
+        status.register_check("fuel", "Block production", Duration::ZERO);
+        status.retain_checks("ethereum", &[String::from("Kept check"), String::from("New check")]);
+
+        let checks: Vec<String> = status.get_checks().into_iter().map(|check| check.check).collect();
+        assert_eq!(checks, vec!["Kept check", "Block production"]);
+    }
+
+    #[test]
+    fn registered_again_checks_keep_their_results() {
+        let status = WatchtowerStatus::new();
+        status.register_check("ethereum", "Block production", Duration::from_secs(60));
+        status.record_success("ethereum", "Block production");
+        status.record_failure("ethereum", "Block production", String::from("no endpoint"));
+        let check_status = status.get_checks()[0].clone();
+        assert!(check_status.succeeded_recently());
+
+        status.register_check("ethereum", "Block production", Duration::from_secs(120));
+        let registered_again = status.get_checks()[0].clone();
+        assert_eq!(registered_again.max_age, Duration::from_secs(120));
+        assert_eq!(registered_again.registered, check_status.registered);
+        assert_eq!(registered_again.last_success, check_status.last_success);
+        assert_eq!(registered_again.last_error.as_deref(), Some("no endpoint"));
+    }
+
+    #[test]
+    fn checks_that_never_succeeded_have_not_succeeded_recently() {
+        let status = WatchtowerStatus::new();
+        status.register_check("ethereum", "Block production", Duration::from_secs(60));
+        let check_status = status.get_checks()[0].clone();
+        assert!(!check_status.is_stale());
+        assert!(!check_status.succeeded_recently());
+    }
+}
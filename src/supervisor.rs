@@ -0,0 +1,180 @@
+use crate::alerts::{AlertLevel, WatchtowerAlerts};
+use crate::status::WatchtowerStatus;
+use crate::WatchtowerConfig;
+
+use anyhow::Result;
+use std::cmp::min;
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+pub static RESTART_DELAY: Duration = Duration::from_millis(1000);
+pub static MAX_RESTART_DELAY: Duration = Duration::from_millis(60000);
+pub static STABLE_RUN_DURATION: Duration = Duration::from_millis(10 * 60 * 1000);
+pub static CRASH_LOOP_RESTARTS: u32 = 3;
+
+// starts a watcher and keeps restarting it (with backoff) whenever it fails to start, crashes or stops, or right away
+// with the new config whenever the config is reloaded. The returned thread finishes once shutdown is signaled
+// (stopping the watcher), or with an error once the watcher has been restarted `max_restarts` times in a row without
+// running stably
+pub fn supervise<F, Fut>(
+    name: &'static str,
+    max_restarts: u32,
+    alerts: WatchtowerAlerts,
+    status: WatchtowerStatus,
+    mut shutdown: watch::Receiver<bool>,
+    mut config: watch::Receiver<WatchtowerConfig>,
+    start: F,
+) -> JoinHandle<Result<()>>
+where
+    F: Fn(WatchtowerConfig) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<JoinHandle<()>>> + Send,
+{
+    tokio::spawn(async move {
+        let log_tag = config.borrow().log_tag();
+        // listed from the start, so a watcher that never manages to start is reported as not running
+        status.set_watcher_running(name, false);
+        let mut restarts: u32 = 0;
+        let mut total_restarts: u64 = 0;
+        let mut restart_delay = RESTART_DELAY;
+        loop {
+            let started = Instant::now();
+            let current_config = config.borrow_and_update().clone();
+            let failure = match start(current_config).await {
+                Ok(mut handle) => {
+                    status.set_watcher_running(name, true);
+                    tokio::select! {
+                        result = &mut handle => match result {
+                            Ok(_) => String::from("stopped unexpectedly"),
+                            Err(e) => format!("crashed ({e})"),
+                        },
+                        _ = shutdown.changed() => {
+                            // stop the watcher so no new checks get scheduled
+                            handle.abort();
+                            let _ = handle.await;
+                            status.set_watcher_running(name, false);
+                            return Ok(());
+                        }
+                        Ok(_) = config.changed() => {
+                            // not a failure, so the watcher is restarted without any backoff
+                            handle.abort();
+                            let _ = handle.await;
+                            status.set_watcher_running(name, false);
+                            log::info!("{}Restarting {} with the reloaded config.", log_tag, name);
+                            continue;
+                        }
+                    }
+                }
+                Err(e) => format!("failed to start ({e})"),
+            };
+            status.set_watcher_running(name, false);
+
+            // a watcher that ran for a while before failing starts over with a fresh restart count
+            if started.elapsed() > STABLE_RUN_DURATION {
+                restarts = 0;
+                restart_delay = RESTART_DELAY;
+            }
+            if restarts >= max_restarts {
+                alerts.alert(
+                    format!("{name} {failure}. Giving up after {restarts} restarts in a row."),
+                    AlertLevel::Error,
+                );
+                return Err(anyhow::anyhow!("{name} {failure}"));
+            }
+
+            restarts += 1;
+            total_restarts += 1;
+            if restarts >= CRASH_LOOP_RESTARTS {
+                alerts.alert(
+                    format!("{name} is crash looping ({restarts} restarts in a row). Last failure: {failure}."),
+                    AlertLevel::Error,
+                );
+            } else {
+                alerts.alert(format!("{name} {failure}."), AlertLevel::Warn);
+            }
+            log::info!(
+                "{}Restarting {} in {} seconds (restart {} of {}, {} total).",
+                log_tag,
+                name,
+                restart_delay.as_secs(),
+                restarts,
+                max_restarts,
+                total_restarts
+            );
+            tokio::select! {
+                _ = tokio::time::sleep(restart_delay) => {}
+                _ = shutdown.changed() => return Ok(()),
+            }
+            restart_delay = min(restart_delay * 2, MAX_RESTART_DELAY);
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::WatchtowerMetrics;
+    use crate::test_utils::test_config;
+    use std::sync::{Arc, Mutex};
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn failing_watchers_restart_with_backoff() {
+        let config = test_config();
+        let alerts = WatchtowerAlerts::new(&config, WatchtowerMetrics::new()).unwrap();
+        let status = WatchtowerStatus::new();
+        let (_shutdown_sender, shutdown) = watch::channel(false);
+        let (_config_sender, config) = watch::channel(config);
+
+        // every run of the watcher stops right after starting, noting whether it was flagged as running meanwhile
+        let starts = Arc::new(Mutex::new(Vec::new()));
+        let running_flags = Arc::new(Mutex::new(Vec::new()));
+        let start_starts = starts.clone();
+        let start_status = status.clone();
+        let start_running_flags = running_flags.clone();
+        let handle = supervise("test", 3, alerts, status.clone(), shutdown, config, move |_| {
+            let status = start_status.clone();
+            let running_flags = start_running_flags.clone();
+            start_starts
+                .lock()
+                .unwrap()
+                .push((Instant::now(), status.get_watchers().get("test").copied()));
+            async move {
+                Ok(tokio::spawn(async move {
+                    running_flags.lock().unwrap().push(status.get_watchers()["test"]);
+                }))
+            }
+        });
+
+        let error = handle.await.unwrap().unwrap_err();
+        assert_eq!(error.to_string(), "test stopped unexpectedly");
+        let starts = starts.lock().unwrap();
+        let delays: Vec<u64> = starts
+            .windows(2)
+            .map(|starts| (starts[1].0 - starts[0].0).as_secs())
+            .collect();
+        assert_eq!(delays, vec![1, 2, 4]);
+        let flags_on_start: Vec<Option<bool>> = starts.iter().map(|start| start.1).collect();
+        assert_eq!(flags_on_start, vec![Some(false); 4]);
+        assert_eq!(*running_flags.lock().unwrap(), vec![true; 4]);
+        assert!(!status.get_watchers()["test"]);
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn watchers_failing_to_start_are_never_running() {
+        let config = test_config();
+        let alerts = WatchtowerAlerts::new(&config, WatchtowerMetrics::new()).unwrap();
+        let status = WatchtowerStatus::new();
+        let (_shutdown_sender, shutdown) = watch::channel(false);
+        let (_config_sender, config) = watch::channel(config);
+
+        let handle = supervise("test", 2, alerts, status.clone(), shutdown, config, |_| async {
+            Err(anyhow::anyhow!("no endpoint"))
+        });
+
+        let error = handle.await.unwrap().unwrap_err();
+        assert_eq!(error.to_string(), "test failed to start (no endpoint)");
+        assert!(!status.get_watchers()["test"]);
+    }
+}
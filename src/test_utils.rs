@@ -0,0 +1,285 @@
+use crate::action_log::WatchtowerActionLog;
+use crate::alerts::WatchtowerAlerts;
+use crate::clients::WatchtowerClients;
+use crate::ethereum_actions::{PausableContract, WatchtowerEthereumActions};
+use crate::metrics::WatchtowerMetrics;
+use crate::network::WatchtowerNetwork;
+use crate::state::WatchtowerState;
+use crate::WatchtowerConfig;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use ethers::abi::{Abi, Address};
+use ethers::types::{Bytes, H256, U256, U64};
+use fuels::prelude::Provider;
+use fuels::test_helpers::setup_test_provider;
+use hyper::header::{HeaderMap, CONTENT_TYPE};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Once};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+static LOGGER: TestLogger = TestLogger;
+static LOGGER_INIT: Once = Once::new();
+static LOGS: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
+
+type Respond = dyn Fn(&MockRequest) -> (u16, Value) + Send + Sync;
+
+// the example config, for tests to change whatever they need
+pub fn test_config() -> WatchtowerConfig {
+    serde_json::from_str(include_str!("../watchtower_config.json.example")).unwrap()
+}
+
+// keeps every record logged from then on (by any test) in memory, for tests checking what gets logged
+struct TestLogger;
+
+impl log::Log for TestLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        LOGS.lock()
+            .unwrap()
+            .push((record.target().to_string(), record.args().to_string()));
+    }
+
+    fn flush(&self) {}
+}
+
+pub fn capture_logs() {
+    LOGGER_INIT.call_once(|| {
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(log::LevelFilter::Info);
+    });
+}
+
+// the messages logged to the target so far (once capture_logs has been called)
+pub fn get_logs(target: &str) -> Vec<String> {
+    LOGS.lock()
+        .unwrap()
+        .iter()
+        .filter(|(log_target, _)| log_target == target)
+        .map(|(_, message)| message.clone())
+        .collect()
+}
+
+// a network set up against an in-process fuel node and a stand-in ethereum endpoint (where no contract is paused), with
+// none of its watchers started
+pub struct TestNetwork {
+    pub network: WatchtowerNetwork,
+    pub ethereum: MockServer,
+    _fuel: Provider,
+}
+
+pub async fn start_test_network(mut config: WatchtowerConfig) -> TestNetwork {
+    let (fuel, fuel_address) = setup_test_provider(vec![], vec![], None, None).await;
+    let ethereum = MockServer::json_rpc(|method, _| match method {
+        "eth_chainId" => Ok(json!(U64::from(5))),
+        "eth_blockNumber" => Ok(json!(U64::from(100))),
+        "eth_call" => Ok(json!(Bytes::from(vec![0u8; 32]))),
+        _ => Err(json!({ "code": -32601, "message": "method not found" })),
+    });
+    config.fuel_graphql = vec![format!("http://{fuel_address}")];
+    config.fuel_graphql_quorum = 1;
+    config.ethereum_rpc = vec![ethereum.url.clone()];
+    config.ethereum_rpc_quorum = 1;
+
+    let metrics = WatchtowerMetrics::new().for_network(config.network_name());
+    let alerts = WatchtowerAlerts::new(&config, metrics.clone()).unwrap();
+    let clients = WatchtowerClients::new(&config).await.unwrap();
+    let state = WatchtowerState::load(&config).unwrap();
+    let actions = WatchtowerEthereumActions::new(&config, &clients, alerts.clone(), metrics, false)
+        .await
+        .unwrap();
+    let (network, _) = WatchtowerNetwork::new(&config, clients, state, alerts, actions);
+
+    TestNetwork {
+        network,
+        ethereum,
+        _fuel: fuel,
+    }
+}
+
+// a contract whose pauses land (or not) once they are sent, which takes `send_delay`
+#[derive(Default)]
+pub struct TestContract {
+    pub paused: Arc<AtomicBool>,
+    pub pauses_land: bool,
+    pub pauses_sent: AtomicUsize,
+    pub send_delay: Duration,
+    pub abi: Abi,
+}
+
+#[async_trait]
+impl PausableContract for TestContract {
+    fn pauser_count(&self) -> usize {
+        1
+    }
+    fn get_pauser_address(&self, _pauser: usize) -> Result<Address> {
+        Ok(Address::zero())
+    }
+    fn get_pause_call(&self) -> Result<(Address, Bytes)> {
+        Ok((Address::zero(), Bytes::default()))
+    }
+    fn get_abi(&self) -> &Abi {
+        &self.abi
+    }
+    async fn check_pauser(&self, _pauser: usize, _min_balance: U256) -> Result<()> {
+        Ok(())
+    }
+    async fn send_pause(&self, _pauser: usize) -> Result<H256> {
+        tokio::time::sleep(self.send_delay).await;
+        self.pauses_sent.fetch_add(1, Ordering::SeqCst);
+        if self.pauses_land {
+            self.paused.store(true, Ordering::SeqCst);
+        }
+        Ok(H256::zero())
+    }
+    async fn confirm_pause(&self, _tx_hash: H256) -> Result<Option<U256>> {
+        Ok(None)
+    }
+    async fn is_paused(&self) -> Result<bool> {
+        Ok(self.paused.load(Ordering::SeqCst))
+    }
+}
+
+// an actions thread where every contract is the same test contract
+pub fn start_actions(contract: Arc<TestContract>) -> (WatchtowerEthereumActions, WatchtowerAlerts) {
+    let config = test_config();
+    let metrics = WatchtowerMetrics::new();
+    let alerts = WatchtowerAlerts::new(&config, metrics.clone()).unwrap();
+    let actions = WatchtowerEthereumActions::start(
+        contract.clone(),
+        contract.clone(),
+        contract,
+        None,
+        U256::zero(),
+        String::from("test"),
+        WatchtowerActionLog::new(&config),
+        alerts.clone(),
+        metrics,
+        false,
+    );
+    (actions, alerts)
+}
+
+// a local http server standing in for the endpoints the watchtower talks to (ethereum RPC, remote signer, Safe
+// transaction service, private relay). Every request is kept for the test to inspect afterwards
+pub struct MockServer {
+    pub url: String,
+    requests: Arc<Mutex<Vec<MockRequest>>>,
+    handle: JoinHandle<()>,
+}
+
+#[derive(Clone, Debug)]
+pub struct MockRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: HeaderMap,
+    pub body: String,
+}
+
+impl MockRequest {
+    pub fn json(&self) -> Value {
+        serde_json::from_str(&self.body).unwrap_or(Value::Null)
+    }
+}
+
+impl MockServer {
+    // answers every request with the status code and json body returned by `respond`
+    pub fn start<F>(respond: F) -> Self
+    where
+        F: Fn(&MockRequest) -> (u16, Value) + Send + Sync + 'static,
+    {
+        let respond: Arc<Respond> = Arc::new(respond);
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let service_requests = requests.clone();
+        let make_service = make_service_fn(move |_| {
+            let respond = respond.clone();
+            let requests = service_requests.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    handle(request, respond.clone(), requests.clone())
+                }))
+            }
+        });
+        let address: SocketAddr = ([127, 0, 0, 1], 0).into();
+        let server = Server::bind(&address).serve(make_service);
+        let url = format!("http://{}", server.local_addr());
+        let handle = tokio::spawn(async move {
+            let _ = server.await;
+        });
+
+        MockServer { url, requests, handle }
+    }
+
+    // answers json-rpc requests with the result (or error object) `respond` returns for the method and params
+    pub fn json_rpc<F>(respond: F) -> Self
+    where
+        F: Fn(&str, &Value) -> Result<Value, Value> + Send + Sync + 'static,
+    {
+        MockServer::start(move |request| {
+            let request = request.json();
+            let method = request["method"].as_str().unwrap_or_default();
+            let response = match respond(method, &request["params"]) {
+                Ok(result) => json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }),
+                Err(error) => json!({ "jsonrpc": "2.0", "id": request["id"], "error": error }),
+            };
+            (200, response)
+        })
+    }
+
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    // the json-rpc requests received for the method
+    pub fn rpc_requests(&self, method: &str) -> Vec<Value> {
+        self.requests()
+            .iter()
+            .map(|request| request.json())
+            .filter(|request| request["method"] == method)
+            .collect()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+async fn handle(
+    request: Request<Body>,
+    respond: Arc<Respond>,
+    requests: Arc<Mutex<Vec<MockRequest>>>,
+) -> Result<Response<Body>, Infallible> {
+    let method = request.method().to_string();
+    let path = match request.uri().path_and_query() {
+        Some(path) => path.to_string(),
+        None => request.uri().path().to_string(),
+    };
+    let headers = request.headers().clone();
+    let body = hyper::body::to_bytes(request.into_body()).await.unwrap_or_default();
+    let request = MockRequest {
+        method,
+        path,
+        headers,
+        body: String::from_utf8_lossy(&body).to_string(),
+    };
+    requests.lock().unwrap().push(request.clone());
+
+    let (status, body) = respond(&request);
+    let response = Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    Ok(response)
+}